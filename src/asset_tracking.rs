@@ -1,8 +1,12 @@
 //! A high-level way to load collections of asset handles as resources.
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, marker::PhantomData};
 
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<ResourceHandles>();
@@ -14,6 +18,14 @@ pub trait LoadResource {
     /// have been loaded, it will be inserted as a resource. This ensures that the resource only
     /// exists when the assets are ready.
     fn load_resource<T: Resource + Asset + Clone + FromWorld>(&mut self) -> &mut Self;
+
+    /// This will load the [`Resource`] as an [`Asset`] from the file at `path`, using
+    /// [`RonAssetLoader`]. It is inserted once loaded, and updated whenever the asset changes,
+    /// e.g. through hot reloading with the `file_watcher` feature.
+    fn load_resource_from_path<T: Resource + Asset + Clone + DeserializeOwned>(
+        &mut self,
+        path: &'static str,
+    ) -> &mut Self;
 }
 
 impl LoadResource for App {
@@ -34,6 +46,94 @@ impl LoadResource for App {
             }));
         self
     }
+
+    fn load_resource_from_path<T: Resource + Asset + Clone + DeserializeOwned>(
+        &mut self,
+        path: &'static str,
+    ) -> &mut Self {
+        self.init_asset::<T>();
+        self.register_asset_loader(RonAssetLoader::<T>::new(extension_of(path)));
+        self.add_systems(PreUpdate, reload_resource_asset::<T>);
+
+        let world = self.world_mut();
+        let handle: Handle<T> = world.resource::<AssetServer>().load(path);
+        world.insert_resource(ResourceHandle(handle.clone()));
+        let mut handles = world.resource_mut::<ResourceHandles>();
+        handles
+            .waiting
+            .push_back((handle.untyped(), |world, handle| {
+                let assets = world.resource::<Assets<T>>();
+                if let Some(value) = assets.get(handle.id().typed::<T>()) {
+                    world.insert_resource(value.clone());
+                }
+            }));
+        self
+    }
+}
+
+/// Everything after the first dot of the file name, e.g. `tuning.ron` for `config/game.tuning.ron`.
+fn extension_of(path: &'static str) -> &'static str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name
+        .split_once('.')
+        .map_or("", |(_, extension)| extension)
+}
+
+/// The handle of a resource loaded with [`LoadResource::load_resource_from_path`].
+#[derive(Resource, Deref)]
+pub struct ResourceHandle<T: Asset>(Handle<T>);
+
+/// Updates a resource loaded from a file whenever its asset is modified.
+fn reload_resource_asset<T: Resource + Asset + Clone>(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<T>>,
+    handle: Res<ResourceHandle<T>>,
+    assets: Res<Assets<T>>,
+) {
+    for event in events.read() {
+        if event.is_modified(handle.id()) {
+            if let Some(value) = assets.get(handle.id()) {
+                info!("Reloaded {}", T::short_type_path());
+                commands.insert_resource(value.clone());
+            }
+        }
+    }
+}
+
+/// Loads assets stored as RON files.
+pub struct RonAssetLoader<T> {
+    extensions: [&'static str; 1],
+    _asset: PhantomData<fn() -> T>,
+}
+
+impl<T> RonAssetLoader<T> {
+    pub fn new(extension: &'static str) -> Self {
+        Self {
+            extensions: [extension],
+            _asset: PhantomData,
+        }
+    }
+}
+
+impl<T: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<T, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
 }
 
 /// A function that inserts a loaded resource.
@@ -1,13 +1,15 @@
-use bevy::prelude::*;
+use bevy::{audio::Volume, prelude::*};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Music>();
     app.register_type::<SoundEffect>();
+    app.register_type::<Engine>();
+    app.register_type::<UiSound>();
+    app.register_type::<VolumeFade>();
+    app.register_type::<VolumeChannels>();
+    app.init_resource::<VolumeChannels>();
 
-    app.add_systems(
-        Update,
-        apply_global_volume.run_if(resource_changed::<GlobalVolume>),
-    );
+    app.add_systems(PostUpdate, apply_volume);
 }
 
 /// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's in the
@@ -24,24 +26,149 @@ pub fn music(handle: Handle<AudioSource>) -> impl Bundle {
 }
 
 /// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's in the
-/// general "sound effect" category (e.g. footsteps, the sound of a magic spell, a door opening).
+/// general "sound effect" category (e.g. crashes, explosions).
 ///
 /// This can then be used to query for and operate on sounds in that category.
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct SoundEffect;
 
-/// A sound effect audio instance.
-pub fn sound_effect(handle: Handle<AudioSource>) -> impl Bundle {
-    (AudioPlayer(handle), PlaybackSettings::DESPAWN, SoundEffect)
+/// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's
+/// the engine loop of a car.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Engine;
+
+/// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's
+/// the sound of the user interface (e.g. hovering or clicking a button).
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct UiSound;
+
+/// A user interface sound instance.
+pub fn ui_sound(handle: Handle<AudioSource>) -> impl Bundle {
+    (AudioPlayer(handle), PlaybackSettings::DESPAWN, UiSound)
+}
+
+/// The categories of sounds with their own volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum AudioChannel {
+    Music,
+    SoundEffects,
+    Engines,
+    Ui,
+}
+
+impl AudioChannel {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Music => "Music",
+            Self::SoundEffects => "Effects",
+            Self::Engines => "Engines",
+            Self::Ui => "Interface",
+        }
+    }
+}
+
+/// Volume of each [`AudioChannel`], on top of the [`GlobalVolume`].
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct VolumeChannels {
+    pub music: Volume,
+    pub sound_effects: Volume,
+    pub engines: Volume,
+    pub ui: Volume,
+}
+
+impl Default for VolumeChannels {
+    fn default() -> Self {
+        Self {
+            music: Volume::Linear(1.0),
+            sound_effects: Volume::Linear(1.0),
+            engines: Volume::Linear(1.0),
+            ui: Volume::Linear(1.0),
+        }
+    }
+}
+
+impl VolumeChannels {
+    pub fn get(&self, channel: AudioChannel) -> Volume {
+        match channel {
+            AudioChannel::Music => self.music,
+            AudioChannel::SoundEffects => self.sound_effects,
+            AudioChannel::Engines => self.engines,
+            AudioChannel::Ui => self.ui,
+        }
+    }
+
+    pub fn get_mut(&mut self, channel: AudioChannel) -> &mut Volume {
+        match channel {
+            AudioChannel::Music => &mut self.music,
+            AudioChannel::SoundEffects => &mut self.sound_effects,
+            AudioChannel::Engines => &mut self.engines,
+            AudioChannel::Ui => &mut self.ui,
+        }
+    }
+
+    /// The volume of a sound with the given markers. Untagged sounds only follow the global volume.
+    fn of(&self, (music, sound_effect, engine, ui): (bool, bool, bool, bool)) -> Volume {
+        let channel = if music {
+            AudioChannel::Music
+        } else if sound_effect {
+            AudioChannel::SoundEffects
+        } else if engine {
+            AudioChannel::Engines
+        } else if ui {
+            AudioChannel::Ui
+        } else {
+            return Volume::Linear(1.0);
+        };
+        self.get(channel)
+    }
 }
 
-/// [`GlobalVolume`] doesn't apply to already-running audio entities, so this system will update them.
-fn apply_global_volume(
+/// Fades a sound in or out on top of its volume, from 0 (silent) to 1 (full volume).
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct VolumeFade(pub f32);
+
+type ChannelMarkers = (Has<Music>, Has<SoundEffect>, Has<Engine>, Has<UiSound>);
+
+/// [`GlobalVolume`] and the [`VolumeChannels`] don't apply to already-running audio entities, so
+/// this system updates them, and sets the volume of new ones. It also applies changes of their
+/// [`PlaybackSettings`] volume and their [`VolumeFade`].
+fn apply_volume(
     global_volume: Res<GlobalVolume>,
-    mut audio_query: Query<(&PlaybackSettings, &mut AudioSink)>,
+    channels: Res<VolumeChannels>,
+    mut audio_query: Query<(
+        Ref<PlaybackSettings>,
+        &mut AudioSink,
+        Option<Ref<VolumeFade>>,
+        ChannelMarkers,
+    )>,
+    mut spatial_audio_query: Query<(
+        Ref<PlaybackSettings>,
+        &mut SpatialAudioSink,
+        Option<Ref<VolumeFade>>,
+        ChannelMarkers,
+    )>,
 ) {
-    for (playback, mut sink) in &mut audio_query {
-        sink.set_volume(global_volume.volume * playback.volume);
+    let changed = global_volume.is_changed() || channels.is_changed();
+    let volume = |playback: &PlaybackSettings, fade: Option<&VolumeFade>, markers| {
+        let fade = Volume::Linear(fade.map_or(1.0, |fade| fade.0));
+        global_volume.volume * channels.of(markers) * playback.volume * fade
+    };
+
+    for (playback, mut sink, fade, markers) in &mut audio_query {
+        let fade_changed = fade.as_ref().is_some_and(|fade| fade.is_changed());
+        if changed || playback.is_changed() || fade_changed || sink.is_added() {
+            sink.set_volume(volume(&playback, fade.as_deref(), markers));
+        }
+    }
+    for (playback, mut sink, fade, markers) in &mut spatial_audio_query {
+        let fade_changed = fade.as_ref().is_some_and(|fade| fade.is_changed());
+        if changed || playback.is_changed() || fade_changed || sink.is_added() {
+            sink.set_volume(volume(&playback, fade.as_deref(), markers));
+        }
     }
 }
@@ -1,31 +1,387 @@
+//! The game camera and the light.
+//!
+//! During a round, players pan the camera by dragging with the right mouse button or by moving
+//! the cursor to the edge of the window, zoom with the mouse wheel and cycle the tilt with T. At
+//! the lowest zoom, the whole road is framed, whatever the aspect ratio of the window.
+//!
+//! Big crashes and explosions shake the camera, unless players turn it off in the settings. During
+//! a slow motion, the camera zooms in on the chain reaction and back out afterwards.
+//!
+//! The camera carries the listener of the spatial audio. Its ears are on the ground in the center
+//! of the view, so sounds pan with their position on the screen, and they fade with their distance
+//! relative to the visible width, whatever the zoom.
+
 use std::f32::consts::PI;
 
 use bevy::{
-    color::palettes::css::ORANGE_RED, pbr::CascadeShadowConfigBuilder, prelude::*,
+    audio::{DefaultSpatialScale, SpatialScale},
+    color::palettes::css::ORANGE_RED,
+    input::{
+        common_conditions::input_just_pressed,
+        mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
+    },
+    pbr::CascadeShadowConfigBuilder,
+    prelude::*,
     render::camera::ScalingMode,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    AppSystems,
+    game::{
+        consts::LANEWIDTH, crash::CarCrash, level::LevelDefinition, pertubator::Explosion,
+        road_network::RoadNetwork, slow_motion::SlowMotion, tuning::GameTuning,
+    },
+    menus::Menu,
+    screens::Screen,
 };
 
+/// Elevation angles of the camera above the ground, in degrees.
+const TILT_PRESETS: [f32; 3] = [75., 60., 45.];
+/// Distance of the camera from the point it looks at. Only matters for clipping.
+const CAMERA_DISTANCE: f32 = 155.;
+const MIN_ZOOM: f32 = 1.;
+const MAX_ZOOM: f32 = 4.;
+/// Zoom factor per line scrolled.
+const ZOOM_STEP: f32 = 1.15;
+/// Width of the window edge in pixels which scrolls the camera.
+const EDGE_SCROLL_MARGIN: f32 = 12.;
+/// Edge scrolling speed in visible widths per second.
+const EDGE_SCROLL_SPEED: f32 = 0.6;
+/// Framing outside of a round.
+const DEFAULT_FRAME: Vec2 = Vec2::new(100., 0.);
+/// Free space framed around the road.
+const FRAME_MARGIN: f32 = LANEWIDTH;
+/// Trauma lost per second.
+const SHAKE_DECAY: f32 = 1.5;
+/// Offset of the camera at full trauma, as a share of the visible width.
+const MAX_SHAKE_OFFSET: f32 = 0.02;
+/// Roll of the camera at full trauma in radians.
+const MAX_SHAKE_ROLL: f32 = 0.05;
+/// Frequency of the shake in Hz.
+const SHAKE_FREQUENCY: f32 = 15.;
+/// Zoom on a chain reaction during a slow motion.
+const SLOW_MOTION_ZOOM: f32 = 2.5;
+/// Distance between the ears of the listener.
+const EAR_GAP: f32 = 4.;
+/// Distance from the center of the view up to which sounds play at full volume, as a share of the
+/// visible width. Beyond it, they fade with the square of the distance.
+const AUDIBLE_SHARE: f32 = 0.25;
+
 pub(super) fn plugin(app: &mut App) {
-    // Spawn and update the zoom of the game camera
+    app.register_type::<CameraRig>();
+    app.register_type::<CameraShake>();
+    app.register_type::<CameraSettings>();
+    app.init_resource::<CameraSettings>();
+
     app.add_systems(Startup, (spawn_camera, spawn_light));
+    app.add_systems(OnEnter(Screen::Gameplay), reset_camera_rig);
+    app.add_systems(OnExit(Screen::Gameplay), calm_camera);
+    app.add_systems(
+        Update,
+        (
+            (
+                zoom_camera,
+                pan_camera,
+                tilt_camera.run_if(input_just_pressed(KeyCode::KeyT)),
+            )
+                .in_set(AppSystems::RecordInput)
+                .run_if(in_state(Screen::Gameplay).and(in_state(Menu::None))),
+            (
+                (shake_on_crashes, shake_on_explosions).run_if(in_state(Screen::Gameplay)),
+                decay_shake,
+                frame_camera,
+                rescale_emitters.run_if(resource_changed::<DefaultSpatialScale>),
+            )
+                .chain()
+                .in_set(AppSystems::Update),
+        ),
+    );
+}
+
+/// Camera options of the player.
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CameraSettings {
+    /// Whether moving the cursor to the edge of the window pans the camera.
+    pub edge_scroll: bool,
+    /// Whether impacts shake the camera.
+    pub shake: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            edge_scroll: true,
+            shake: true,
+        }
+    }
+}
+
+/// Where the player looks at. The camera's transform and projection follow from it.
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraRig {
+    /// The point on the ground (x, z) in the center of the view.
+    pub focus: Vec2,
+    /// 1 frames the whole road, larger values zoom in.
+    pub zoom: f32,
+    /// Index into the tilt presets.
+    pub tilt: usize,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: Vec2::ZERO,
+            zoom: MIN_ZOOM,
+            tilt: 0,
+        }
+    }
+}
+
+impl CameraRig {
+    /// Elevation of the camera above the ground in radians.
+    fn elevation(&self) -> f32 {
+        TILT_PRESETS[self.tilt % TILT_PRESETS.len()].to_radians()
+    }
+}
+
+/// Shakes the camera by the square of the trauma, which decays over time.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraShake {
+    /// From 0 (calm) to 1 (the strongest shake).
+    pub trauma: f32,
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0., 1.);
+    }
 }
 
 fn spawn_camera(mut commands: Commands) {
     commands.spawn((
         Name::new("Camera"),
         Camera3d::default(),
+        CameraRig::default(),
+        CameraShake::default(),
         Projection::from(OrthographicProjection {
-            // 6 world units per pixel of window height.
-            scaling_mode: ScalingMode::FixedHorizontal {
-                viewport_width: 100.0,
+            // Set by `frame_camera`.
+            scaling_mode: ScalingMode::AutoMin {
+                min_width: DEFAULT_FRAME.x,
+                min_height: DEFAULT_FRAME.y,
             },
             ..OrthographicProjection::default_3d()
         }),
         Transform::from_xyz(0.0, 150.0, 40.0).looking_at(Vec3::ZERO, Vec3::Y),
         MeshPickingCamera,
+        // The camera looks along its -z axis onto the ground.
+        SpatialListener {
+            left_ear_offset: Vec3::new(-EAR_GAP / 2., 0., -CAMERA_DISTANCE),
+            right_ear_offset: Vec3::new(EAR_GAP / 2., 0., -CAMERA_DISTANCE),
+        },
     ));
 }
 
+/// Every round starts with the whole road in view. The tilt is kept.
+fn reset_camera_rig(mut rig: Single<&mut CameraRig>) {
+    rig.focus = Vec2::ZERO;
+    rig.zoom = MIN_ZOOM;
+}
+
+fn zoom_camera(mut rig: Single<&mut CameraRig>, scroll: Res<AccumulatedMouseScroll>) {
+    let lines = match scroll.unit {
+        MouseScrollUnit::Pixel => scroll.delta.y / 100.0,
+        MouseScrollUnit::Line => scroll.delta.y,
+    };
+    if lines == 0. {
+        return;
+    }
+
+    rig.zoom = (rig.zoom * ZOOM_STEP.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+}
+
+fn pan_camera(
+    rig: Single<(&mut CameraRig, &Projection)>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    settings: Res<CameraSettings>,
+    time: Res<Time<Real>>,
+) {
+    let (mut rig, projection) = rig.into_inner();
+    let Projection::Orthographic(ortho) = projection else {
+        return;
+    };
+    // Moving the cursor vertically moves along z, foreshortened by the tilt.
+    let world_per_pixel =
+        ortho.area.width() / window.width() * Vec2::new(1., 1. / rig.elevation().sin());
+
+    if mouse.pressed(MouseButton::Right) {
+        // The ground follows the cursor.
+        rig.focus -= motion.delta * world_per_pixel;
+        return;
+    }
+
+    if !settings.edge_scroll {
+        return;
+    }
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let size = window.size();
+    let direction = Vec2::new(
+        edge_direction(cursor.x, size.x),
+        edge_direction(cursor.y, size.y),
+    );
+    rig.focus +=
+        direction * EDGE_SCROLL_SPEED * window.width() * world_per_pixel * time.delta_secs();
+}
+
+/// -1 at the start of the window, 1 at the end and 0 in between.
+fn edge_direction(position: f32, size: f32) -> f32 {
+    if position < EDGE_SCROLL_MARGIN {
+        -1.
+    } else if position > size - EDGE_SCROLL_MARGIN {
+        1.
+    } else {
+        0.
+    }
+}
+
+fn tilt_camera(mut rig: Single<&mut CameraRig>) {
+    rig.tilt = (rig.tilt + 1) % TILT_PRESETS.len();
+}
+
+fn shake_on_crashes(
+    mut shake: Single<&mut CameraShake>,
+    mut car_crashes: EventReader<CarCrash>,
+    tuning: Res<GameTuning>,
+) {
+    for car_crash in car_crashes.read() {
+        shake.add_trauma(car_crash.severity / tuning.shake_crash_severity);
+    }
+}
+
+/// Explosions shake the camera less the farther they are from the center of the view.
+fn shake_on_explosions(
+    camera: Single<(&mut CameraShake, &CameraRig)>,
+    explosions: Query<&Transform, Added<Explosion>>,
+    tuning: Res<GameTuning>,
+) {
+    let (mut shake, rig) = camera.into_inner();
+    for transform in &explosions {
+        let distance = transform.translation.xz().distance(rig.focus);
+        let closeness = 1. - (distance / tuning.shake_explosion_range).clamp(0., 1.);
+        shake.add_trauma(tuning.shake_explosion_trauma * closeness);
+    }
+}
+
+fn decay_shake(mut shake: Single<&mut CameraShake>, time: Res<Time<Real>>) {
+    if shake.trauma > 0. {
+        shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_secs()).max(0.);
+    }
+}
+
+fn calm_camera(mut shake: Single<&mut CameraShake>) {
+    shake.trauma = 0.;
+}
+
+/// Keeps the rig inside the level and places the camera accordingly.
+fn frame_camera(
+    camera: Single<(
+        &mut CameraRig,
+        &CameraShake,
+        &mut Transform,
+        &mut Projection,
+    )>,
+    level: Option<Res<LevelDefinition>>,
+    network: Option<Res<RoadNetwork>>,
+    screen: Res<State<Screen>>,
+    settings: Res<CameraSettings>,
+    slow_motion: Res<SlowMotion>,
+    mut spatial_scale: ResMut<DefaultSpatialScale>,
+    time: Res<Time<Real>>,
+) {
+    let (mut rig, shake, mut transform, mut projection) = camera.into_inner();
+
+    // The paths of the road network may reach beyond the straight road, e.g. at junctions.
+    let bounds = match level {
+        Some(level) if *screen.get() == Screen::Gameplay => {
+            let road = Vec2::new(level.road_length, level.road_width());
+            let paths = network.map_or(Rect::EMPTY, |network| network.bounds());
+            Rect::from_center_size(Vec2::ZERO, road)
+                .union(paths)
+                .inflate(FRAME_MARGIN)
+        }
+        _ => Rect::from_center_size(Vec2::ZERO, DEFAULT_FRAME),
+    };
+    let (center, frame) = (bounds.center(), bounds.size());
+
+    // The view never leaves the framed area.
+    let max_offset = frame / 2. * (1. - 1. / rig.zoom);
+    let focus = rig.focus.clamp(center - max_offset, center + max_offset);
+    if rig.focus != focus {
+        rig.focus = focus;
+    }
+
+    // The slow motion eases in and out, and the camera with it. The rig stays where the player
+    // left it.
+    let blend = slow_motion.intensity();
+    let zoom = rig.zoom.lerp(rig.zoom.max(SLOW_MOTION_ZOOM), blend);
+    let max_offset = frame / 2. * (1. - 1. / zoom);
+    let focus = focus
+        .lerp(slow_motion.focus, blend)
+        .clamp(center - max_offset, center + max_offset);
+
+    let elevation = rig.elevation();
+    let target = Vec3::new(focus.x, 0., focus.y);
+    let direction = Vec3::new(0., elevation.sin(), elevation.cos());
+    *transform = Transform::from_translation(target + direction * CAMERA_DISTANCE)
+        .looking_at(target, Vec3::Y);
+
+    if settings.shake && shake.trauma > 0. {
+        // Overlapping sines of unrelated frequencies look random enough.
+        let t = time.elapsed_secs() * SHAKE_FREQUENCY;
+        let noise = |seed: f32| ((t + seed).sin() + (2.3 * t + 3. * seed).sin()) / 2.;
+        let strength = shake.trauma * shake.trauma;
+        let offset = MAX_SHAKE_OFFSET * frame.x / zoom * strength;
+        let translation =
+            transform.right() * noise(0.) * offset + transform.up() * noise(7.) * offset;
+        transform.translation += translation;
+        transform.rotate_local_z(MAX_SHAKE_ROLL * strength * noise(13.));
+    }
+
+    if let Projection::Orthographic(ortho) = projection.as_mut() {
+        // The depth of the frame is foreshortened by the tilt.
+        ortho.scaling_mode = ScalingMode::AutoMin {
+            min_width: frame.x,
+            min_height: frame.y * elevation.sin(),
+        };
+        ortho.scale = 1. / zoom;
+    }
+
+    let audible_distance = AUDIBLE_SHARE * frame.x / zoom;
+    let scale = Vec3::splat(1. / audible_distance);
+    if spatial_scale.0.0 != scale {
+        spatial_scale.0 = SpatialScale(scale);
+    }
+}
+
+/// Moves the spatial sounds to their positions at the new scale. The audio plugin only updates
+/// the ears, and the sounds which move.
+fn rescale_emitters(
+    emitters: Query<(&GlobalTransform, &SpatialAudioSink, &PlaybackSettings)>,
+    spatial_scale: Res<DefaultSpatialScale>,
+) {
+    for (transform, sink, settings) in &emitters {
+        let scale = settings.spatial_scale.unwrap_or(spatial_scale.0).0;
+        sink.set_emitter_position(transform.translation() * scale);
+    }
+}
+
 fn spawn_light(mut commands: Commands) {
     // ambient light
     commands.insert_resource(AmbientLight {
@@ -0,0 +1,104 @@
+//! Bullet time, slowing the game down at the player's command.
+//!
+//! Players toggle it with Space to place pertubators precisely in front of fast traffic. It drains
+//! a meter in real time, which crashes refill. Like the other effects it only sets its factor of
+//! the [`TimeScale`], while pausing still stops the clocks as usual and keeps the meter.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{AppSystems, PausableSystems, menus::Menu, screens::Screen};
+
+use super::{crash::CarCrash, replay::ReplayPlayback, time_scale::TimeScale, tuning::GameTuning};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<BulletTime>();
+    app.init_resource::<BulletTime>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), reset_bullet_time);
+    app.add_systems(OnExit(Screen::Gameplay), reset_bullet_time);
+    app.add_systems(
+        Update,
+        (
+            toggle_bullet_time.in_set(AppSystems::RecordInput).run_if(
+                in_state(Menu::None)
+                    .and(not(resource_exists::<ReplayPlayback>))
+                    .and(input_just_pressed(KeyCode::Space)),
+            ),
+            drain_bullet_time.in_set(AppSystems::TickTimers),
+        )
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        FixedUpdate,
+        refill_bullet_time
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The bullet time meter of the player.
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct BulletTime {
+    /// From 0 (empty) to 1 (full).
+    pub meter: f32,
+    pub active: bool,
+}
+
+impl Default for BulletTime {
+    fn default() -> Self {
+        Self {
+            meter: 1.,
+            active: false,
+        }
+    }
+}
+
+fn reset_bullet_time(mut bullet_time: ResMut<BulletTime>, mut time_scale: ResMut<TimeScale>) {
+    *bullet_time = BulletTime::default();
+    time_scale.bullet_time = 1.;
+}
+
+fn toggle_bullet_time(
+    mut bullet_time: ResMut<BulletTime>,
+    mut time_scale: ResMut<TimeScale>,
+    tuning: Res<GameTuning>,
+) {
+    bullet_time.active = !bullet_time.active && bullet_time.meter > 0.;
+    time_scale.bullet_time = if bullet_time.active {
+        tuning.bullet_time_speed
+    } else {
+        1.
+    };
+}
+
+/// Drains the meter in real time, so it lasts as long however slow the game runs.
+fn drain_bullet_time(
+    mut bullet_time: ResMut<BulletTime>,
+    mut time_scale: ResMut<TimeScale>,
+    tuning: Res<GameTuning>,
+    time: Res<Time<Real>>,
+) {
+    if !bullet_time.active {
+        return;
+    }
+    bullet_time.meter -= time.delta_secs() / tuning.bullet_time_seconds;
+    if bullet_time.meter <= 0. {
+        bullet_time.meter = 0.;
+        bullet_time.active = false;
+        time_scale.bullet_time = 1.;
+    }
+}
+
+fn refill_bullet_time(
+    mut car_crashes: EventReader<CarCrash>,
+    mut bullet_time: ResMut<BulletTime>,
+    tuning: Res<GameTuning>,
+) {
+    for car_crash in car_crashes.read() {
+        bullet_time.meter =
+            (bullet_time.meter + car_crash.severity * tuning.bullet_time_refill).min(1.);
+    }
+}
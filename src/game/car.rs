@@ -1,30 +1,36 @@
 use std::f32::consts::PI;
 
 use avian3d::prelude::*;
-use bevy::{math::ops::acos, prelude::*};
+use bevy::{
+    math::ops::{self, acos},
+    prelude::*,
+};
 use rand::Rng;
 
 use crate::{
     AppSystems, PausableSystems,
     asset_tracking::LoadResource,
-    game::{consts::MAXIMALANGULARVELOCITYFORTORQUECORRECTION, util::Lifetime},
+    audio::{Engine, SoundEffect},
+    game::{consts::MAXIMALANGULARVELOCITYFORTORQUECORRECTION, round::GameRng, util::Lifetime},
     screens::Screen,
 };
 
 use super::{
     car_colliders::{AllCarColliders, WheelCollider},
     consts::{
-        CARBODYFRICTION, CARFORWARDFORCE, INITIALCARMODELROTATION,
-        MAXIMALYAXISANGLEOFFSETFORTORQUECORRECTION, MAXWRECKINGTHRESHOLD,
-        MINIMALANGLEOFFSETFORTORQUECORRECTION, WHEELFRICTIONNAILED, WHEELFRICTIONSOAPED,
-        WHEELFRICTIONSOAPEDANDNAILED,
+        INITIALCARMODELROTATION, MAXIMALYAXISANGLEOFFSETFORTORQUECORRECTION,
+        MINIMALANGLEOFFSETFORTORQUECORRECTION,
     },
+    crash::{CarCrash, CarCrashable, CarLanded, PreStepVelocity},
+    engine_sound::EngineArchetype,
+    layers::GameLayer,
     pertubator::{Nailed, Soaped},
+    surface::OnSurface,
+    tuning::GameTuning,
 };
 
-const CRASH_SOUND_MAGNITUDE_CUTOFF_1: f32 = 10.0;
-const CRASH_SOUND_MAGNITUDE_CUTOFF_2: f32 = 20.0;
-const DEBRIS_IMPULSE: f32 = 50.0;
+/// Radius of the bolts and nuts flying off a crash, before scaling.
+const DEBRIS_RADIUS: f32 = 0.05;
 
 #[derive(Debug, Default, Component, Reflect)]
 #[reflect(Component)]
@@ -35,6 +41,19 @@ pub struct Wrecked;
 pub struct Car {
     target_velocity: f32,
     driving_direction: Vec3, // This has to be a normalized vector!
+    /// Whether the car brakes to let other cars pass, e.g. at a merge.
+    yielding: bool,
+}
+
+impl Car {
+    /// Lets the car drive in the new normalized direction.
+    pub fn steer(&mut self, driving_direction: Vec3) {
+        self.driving_direction = driving_direction;
+    }
+
+    pub fn set_yielding(&mut self, yielding: bool) {
+        self.yielding = yielding;
+    }
 }
 
 pub(super) fn plugin(app: &mut App) {
@@ -43,10 +62,17 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<CarAssets>();
     app.load_resource::<CarAssets>();
 
-    // TODO: Put this in the right schedule
+    // Everything influencing the simulation runs in `FixedUpdate`, so a round can be replayed.
     app.add_systems(
         FixedUpdate,
-        (accelerate_cars, correct_car_torque, update_friction_changes)
+        (
+            accelerate_cars,
+            correct_car_torque,
+            update_friction_changes,
+            apply_friction_tuning.run_if(resource_exists_and_changed::<GameTuning>),
+            wreck_crashing_cars,
+            spawn_debris_on_crash,
+        )
             .in_set(AppSystems::Update)
             .in_set(PausableSystems)
             .run_if(in_state(Screen::Gameplay)),
@@ -56,8 +82,6 @@ pub(super) fn plugin(app: &mut App) {
         Update,
         (
             play_crash_sound,
-            wreck_crashing_cars,
-            spawn_debris_on_crash,
             spawn_smoke_on_wrecked,
             remove_audio_on_wrecked,
             rotate_y,
@@ -67,10 +91,6 @@ pub(super) fn plugin(app: &mut App) {
             .run_if(in_state(Screen::Gameplay)),
     );
 
-    app.register_type::<CarCrashable>();
-    app.register_type::<CarCrash>();
-    app.add_event::<CarCrash>();
-
     app.register_type::<Wrecked>();
     app.register_type::<RotateY>();
 }
@@ -79,6 +99,8 @@ pub fn spawn_car(
     entity_commands: &mut EntityCommands,
     car_assets: &CarAssets,
     all_car_colliders: &AllCarColliders,
+    tuning: &GameTuning,
+    rng: &mut GameRng,
     init_pos: Vec3,
     target_velocity: f32,
     driving_direction: Vec3,
@@ -87,39 +109,42 @@ pub fn spawn_car(
         .insert(create_car(
             car_assets,
             all_car_colliders,
+            tuning,
+            rng,
             init_pos,
             target_velocity,
             driving_direction,
         ))
-        .insert((CollisionEventsEnabled, CarCrashable))
-        .observe(car_observer_crash);
+        .insert((
+            CollisionEventsEnabled,
+            CarCrashable,
+            PreStepVelocity::default(),
+            OnSurface::default(),
+        ));
 }
 
 /// Returns a bundle representing a car.
 pub fn create_car(
     car_assets: &CarAssets,
     all_car_colliders: &AllCarColliders,
+    tuning: &GameTuning,
+    rng: &mut GameRng,
     init_pos: Vec3,
     target_velocity: f32,
     driving_direction: Vec3,
 ) -> impl Bundle {
-    let rng = &mut rand::thread_rng();
-
     let car_index = rng.gen_range(0..car_assets.get_scenes().len());
     let scene_handle = car_assets.vehicles[car_index].clone();
     let colliders = &all_car_colliders[car_index];
-    /* TODO: This does not work correctly? */
-    let rotation = if driving_direction == Vec3::X {
-        INITIALCARMODELROTATION
-    } else {
-        INITIALCARMODELROTATION + PI
-    };
+    let (_, archetype) = CAR_MODELS[car_index];
+    let rotation = INITIALCARMODELROTATION + ops::atan2(-driving_direction.z, driving_direction.x);
 
     (
         Name::new("Car"),
         Car {
             target_velocity,
             driving_direction,
+            yielding: false,
         },
         StateScoped(Screen::Gameplay),
         // Physics
@@ -129,35 +154,53 @@ pub fn create_car(
             scale: Vec3::splat(0.8),
         },
         RigidBody::Dynamic,
+        TransformInterpolation,
         colliders.body.clone(),
+        GameLayer::Car.collision_layers(),
         children![
-            colliders.get_wheel_bl_bundle(),
-            colliders.get_wheel_br_bundle(),
-            colliders.get_wheel_fl_bundle(),
-            colliders.get_wheel_fr_bundle(),
+            colliders.get_wheel_bl_bundle(tuning.wheel_friction_normal),
+            colliders.get_wheel_br_bundle(tuning.wheel_friction_normal),
+            colliders.get_wheel_fl_bundle(tuning.wheel_friction_normal),
+            colliders.get_wheel_fr_bundle(tuning.wheel_friction_normal),
         ],
         LinearVelocity::default(),
         ExternalForce::default().with_persistence(false),
         ExternalTorque::new(Vec3::ZERO).with_persistence(false),
-        Friction::new(CARBODYFRICTION),
+        Friction::new(tuning.car_body_friction),
         MaxAngularSpeed(4. * 2. * PI),
-        // Gfx and audio
-        SceneRoot(scene_handle),
-        AudioPlayer::new(car_assets.engine_audio.clone()),
-        PlaybackSettings::LOOP
-            .with_volume(bevy::audio::Volume::Decibels(-24.))
-            .with_speed(rng.gen_range(0.1..0.8)),
+        // Gfx and audio, nested to stay within the size of a bundle tuple
+        (
+            SceneRoot(scene_handle),
+            AudioPlayer::new(car_assets.engine_audio.clone()),
+            // Pitch and volume follow the speed, see `engine_sound`.
+            PlaybackSettings::LOOP.with_spatial(true),
+            Engine,
+            archetype,
+        ),
     )
 }
 
 /// Applies the driving force to the cars being not wrecked.
-fn accelerate_cars(mut cars: Query<(&Car, &LinearVelocity, &mut ExternalForce, Has<Wrecked>)>) {
+fn accelerate_cars(
+    mut cars: Query<(&Car, &LinearVelocity, &mut ExternalForce, Has<Wrecked>)>,
+    tuning: Res<GameTuning>,
+) {
     for (car, velocity, mut applied_force, has_wrecked) in cars.iter_mut() {
-        if has_wrecked || velocity.length() > car.target_velocity {
+        if has_wrecked {
+            continue;
+        }
+        if car.yielding {
+            // Brake until the way is clear.
+            let new_force =
+                applied_force.force() - velocity.normalize_or_zero() * tuning.car_forward_force;
+            applied_force.set_force(new_force);
+            continue;
+        }
+        if velocity.length() > car.target_velocity {
             continue;
         }
         // Let the car accelerate in the trageted direction.
-        let new_force = applied_force.force() + car.driving_direction * CARFORWARDFORCE;
+        let new_force = applied_force.force() + car.driving_direction * tuning.car_forward_force;
         applied_force.set_force(new_force);
     }
 }
@@ -226,24 +269,21 @@ fn update_friction_changes(
         ),
         Or<(Added<Soaped>, Added<Nailed>)>,
     >,
+    surfaces: Query<&OnSurface>,
+    tuning: Res<GameTuning>,
 ) {
     for (mut friction, possible_parent, is_wheel, is_soaped, is_nailed) in
         changed_objects.iter_mut()
     {
-        let mut set_friction = |val| {
-            friction.dynamic_coefficient = val;
-            friction.static_coefficient = val;
-        };
-
+        let surface = possible_parent
+            .and_then(|parent| surfaces.get(parent.parent()).ok())
+            .map(|surface| surface.0)
+            .unwrap_or_default();
         // The wheel friction will be applied, if its a wheel or not.
-        if is_soaped && is_nailed {
-            set_friction(WHEELFRICTIONSOAPEDANDNAILED);
-        } else if is_soaped {
-            set_friction(WHEELFRICTIONSOAPED);
-        } else {
-            // Has to be nailed, if Added<Nailed> and not Added<Soaped>
-            set_friction(WHEELFRICTIONNAILED);
-        }
+        set_friction(
+            &mut friction,
+            tuning.wheel_friction(is_soaped, is_nailed, surface),
+        );
 
         // Part of a car -> mark it as wrecked.
         if is_wheel && possible_parent.is_some() {
@@ -256,6 +296,34 @@ fn update_friction_changes(
     }
 }
 
+/// Applies changed friction values of the [`GameTuning`] to the cars already on the road.
+fn apply_friction_tuning(
+    mut wheels: Query<(&mut Friction, &ChildOf, Has<Soaped>, Has<Nailed>), With<WheelCollider>>,
+    mut bodies: Query<&mut Friction, (With<Car>, Without<WheelCollider>)>,
+    surfaces: Query<&OnSurface>,
+    tuning: Res<GameTuning>,
+) {
+    for (mut friction, child_of, is_soaped, is_nailed) in &mut wheels {
+        let surface = surfaces
+            .get(child_of.parent())
+            .map(|surface| surface.0)
+            .unwrap_or_default();
+        set_friction(
+            &mut friction,
+            tuning.wheel_friction(is_soaped, is_nailed, surface),
+        );
+    }
+
+    for mut friction in &mut bodies {
+        set_friction(&mut friction, tuning.car_body_friction);
+    }
+}
+
+pub(super) fn set_friction(friction: &mut Friction, value: f32) {
+    friction.dynamic_coefficient = value;
+    friction.static_coefficient = value;
+}
+
 #[derive(Debug, Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct CarAssets {
@@ -264,6 +332,8 @@ pub struct CarAssets {
     #[dependency]
     engine_audio: Handle<AudioSource>,
     #[dependency]
+    pub tire_screech_audio: Handle<AudioSource>,
+    #[dependency]
     crash_audio: Vec<Handle<AudioSource>>,
     #[dependency]
     pub explosion_audio: Handle<AudioSource>,
@@ -281,27 +351,28 @@ impl CarAssets {
     }
 }
 
-const CAR_MODELS: &[&str] = &[
-    "ambulance",
-    "delivery",
-    "delivery-flat",
-    "firetruck",
-    "garbage-truck",
-    "hatchback-sports",
-    "police",
-    "race",
-    "race-future",
-    "sedan",
-    "sedan-sports",
-    "suv",
-    "suv-luxury",
-    "taxi",
-    "tractor",
-    "tractor-police",
-    "tractor-shovel",
-    "truck",
-    "truck-flat",
-    "van",
+/// The car models with the sound of their engines.
+const CAR_MODELS: &[(&str, EngineArchetype)] = &[
+    ("ambulance", EngineArchetype::Van),
+    ("delivery", EngineArchetype::Van),
+    ("delivery-flat", EngineArchetype::Van),
+    ("firetruck", EngineArchetype::Heavy),
+    ("garbage-truck", EngineArchetype::Heavy),
+    ("hatchback-sports", EngineArchetype::Sports),
+    ("police", EngineArchetype::Compact),
+    ("race", EngineArchetype::Sports),
+    ("race-future", EngineArchetype::Sports),
+    ("sedan", EngineArchetype::Compact),
+    ("sedan-sports", EngineArchetype::Sports),
+    ("suv", EngineArchetype::Van),
+    ("suv-luxury", EngineArchetype::Van),
+    ("taxi", EngineArchetype::Compact),
+    ("tractor", EngineArchetype::Tractor),
+    ("tractor-police", EngineArchetype::Tractor),
+    ("tractor-shovel", EngineArchetype::Tractor),
+    ("truck", EngineArchetype::Heavy),
+    ("truck-flat", EngineArchetype::Heavy),
+    ("van", EngineArchetype::Van),
 ];
 
 impl FromWorld for CarAssets {
@@ -310,7 +381,7 @@ impl FromWorld for CarAssets {
         Self {
             vehicles: CAR_MODELS
                 .iter()
-                .map(|model| {
+                .map(|(model, _)| {
                     assets.load(
                         GltfAssetLabel::Scene(0)
                             .from_asset(format!("models/vehicles/{}.glb", model)),
@@ -318,6 +389,7 @@ impl FromWorld for CarAssets {
                 })
                 .collect(),
             engine_audio: assets.load("audio/sound_effects/engine-loop.ogg"),
+            tire_screech_audio: assets.load("audio/sound_effects/crash/car screech.ogg"),
             crash_audio: vec![
                 assets.load("audio/sound_effects/crash/small crash.ogg"),
                 assets.load("audio/sound_effects/crash/medium_crash.ogg"),
@@ -334,80 +406,20 @@ impl FromWorld for CarAssets {
     }
 }
 
-#[derive(Debug, Default, Component, Reflect)]
-#[reflect(Component)]
-pub struct CarCrashable;
-
-// TODO: This can contain other things than cars, so you need to filter for that when reading
-#[derive(Debug, Event, Reflect)]
-pub struct CarCrash {
-    pub entities: [Entity; 2],
-    pub magnitude: f32,
-}
-
-/// To be added to a car entity
-/// Will observer all car related collisions and update score based on total collision impulse
-/// TODO: Does this actually trigger twice on a car on car collision?
-fn car_observer_crash(
-    trigger: Trigger<OnCollisionStart>,
-    mut car_crash_writer: EventWriter<CarCrash>,
-    car_crashables: Query<Entity, With<CarCrashable>>,
-    collisions: Collisions,
-) {
-    let car = trigger.target();
-    let other_entity = trigger.collider;
-
-    /* Allow any car on car crashable collision */
-    if car_crashables.contains(other_entity) {
-        if let Some(contact_pair) = collisions.get(car, other_entity) {
-            let event = CarCrash {
-                entities: [car, other_entity],
-                magnitude: contact_pair.total_normal_impulse_magnitude(),
-            };
-
-            car_crash_writer.write(event);
-        }
-    }
-}
-
 fn wreck_crashing_cars(
     mut commands: Commands,
-    // All car parts that are not marked as wrecked
-    car_parts: Query<
-        (Option<&Car>, Option<&ChildOf>),
-        (Or<(With<Car>, With<WheelCollider>)>, Without<Wrecked>),
-    >,
+    intact_cars: Query<(), (With<Car>, Without<Wrecked>)>,
     mut car_crashes: EventReader<CarCrash>,
+    tuning: Res<GameTuning>,
 ) {
     for car_crash in car_crashes.read() {
-        if car_crash.magnitude < MAXWRECKINGTHRESHOLD {
+        if car_crash.severity < tuning.wrecking_threshold {
             continue;
         }
 
-        let one_entity = car_crash.entities[0];
-        let other_entity = car_crash.entities[1];
-
-        if let Ok((car_opt, parent_opt)) = car_parts.get(one_entity) {
-            let car_entity = if car_opt.is_some() {
-                one_entity
-            } else {
-                parent_opt.unwrap().0
-            };
-
-            if car_parts.contains(car_entity) {
-                commands.entity(car_entity).insert(Wrecked);
-            }
-        }
-
-        if let Ok((car_opt, parent_opt)) = car_parts.get(other_entity) {
-            let car_entity = if car_opt.is_some() {
-                other_entity
-            } else {
-                parent_opt.unwrap().0
-            };
-
-            if car_parts.contains(car_entity) {
-                commands.entity(car_entity).insert(Wrecked);
+        for entity in [car_crash.car, car_crash.other] {
+            if intact_cars.contains(entity) {
+                commands.entity(entity).insert(Wrecked);
             }
         }
     }
@@ -417,13 +429,24 @@ fn play_crash_sound(
     mut commands: Commands,
     transforms: Query<&Transform, (With<Car>, Without<Wrecked>)>,
     mut car_crashes: EventReader<CarCrash>,
+    mut car_landings: EventReader<CarLanded>,
     car_assets: Res<CarAssets>,
+    tuning: Res<GameTuning>,
 ) {
-    for car_crash in car_crashes.read() {
-        if let Ok(transform) = transforms.get(car_crash.entities[0]) {
-            let audio_source_index = if car_crash.magnitude < CRASH_SOUND_MAGNITUDE_CUTOFF_1 {
+    let [small_cutoff, medium_cutoff] = tuning.crash_sound_cutoffs;
+    let impacts = car_crashes
+        .read()
+        .map(|car_crash| (car_crash.car, car_crash.severity))
+        .chain(
+            car_landings
+                .read()
+                .map(|car_landed| (car_landed.car, car_landed.severity)),
+        );
+    for (car, severity) in impacts {
+        if let Ok(transform) = transforms.get(car) {
+            let audio_source_index = if severity < small_cutoff {
                 0
-            } else if car_crash.magnitude < CRASH_SOUND_MAGNITUDE_CUTOFF_2 {
+            } else if severity < medium_cutoff {
                 1
             } else {
                 2
@@ -435,7 +458,10 @@ fn play_crash_sound(
                 *transform,
                 Lifetime::new(1.0),
                 AudioPlayer::new(car_assets.crash_audio[audio_source_index].clone()),
-                PlaybackSettings::ONCE.with_volume(bevy::audio::Volume::Decibels(-23.)),
+                PlaybackSettings::ONCE
+                    .with_volume(bevy::audio::Volume::Decibels(-23.))
+                    .with_spatial(true),
+                SoundEffect,
             ));
         }
     }
@@ -448,11 +474,12 @@ fn spawn_debris_on_crash(
     transforms: Query<&Transform, (With<Car>, Without<Wrecked>)>,
     mut car_crashes: EventReader<CarCrash>,
     car_assets: Res<CarAssets>,
+    tuning: Res<GameTuning>,
 ) {
     for car_crash in car_crashes.read() {
         let transforms: Vec<&Transform> = [
-            transforms.get(car_crash.entities[0]),
-            transforms.get(car_crash.entities[1]),
+            transforms.get(car_crash.car),
+            transforms.get(car_crash.other),
         ]
         .iter()
         .filter_map(|res| res.ok())
@@ -469,8 +496,11 @@ fn spawn_debris_on_crash(
                 },
                 Lifetime::new(4.0),
                 RigidBody::Dynamic,
+                TransformInterpolation,
+                Collider::sphere(DEBRIS_RADIUS),
+                GameLayer::Debris.collision_layers(),
                 Mass(10.0),
-                ExternalImpulse::new(DEBRIS_IMPULSE * Vec3::new(-1., 1., 1.).normalize())
+                ExternalImpulse::new(tuning.debris_impulse * Vec3::new(-1., 1., 1.).normalize())
                     .with_persistence(false),
                 SceneRoot(car_assets.bolt.clone()),
             ));
@@ -485,8 +515,11 @@ fn spawn_debris_on_crash(
                 },
                 Lifetime::new(4.0),
                 RigidBody::Dynamic,
+                TransformInterpolation,
+                Collider::sphere(DEBRIS_RADIUS),
+                GameLayer::Debris.collision_layers(),
                 Mass(10.0),
-                ExternalImpulse::new(DEBRIS_IMPULSE * Vec3::new(1., 1., -1.).normalize())
+                ExternalImpulse::new(tuning.debris_impulse * Vec3::new(1., 1., -1.).normalize())
                     .with_persistence(false),
                 SceneRoot(car_assets.nut.clone()),
             ));
@@ -525,10 +558,15 @@ fn spawn_smoke_on_wrecked(
     }
 }
 
+/// The engine of a wrecked car dies. Its tires keep screeching while they slide.
 fn remove_audio_on_wrecked(mut commands: Commands, wrecked_cars: Query<Entity, Added<Wrecked>>) {
     for wrecked_car in wrecked_cars {
-        commands
-            .entity(wrecked_car)
-            .remove::<(AudioPlayer, PlaybackSettings, AudioSink)>();
+        commands.entity(wrecked_car).remove::<(
+            AudioPlayer,
+            PlaybackSettings,
+            AudioSink,
+            SpatialAudioSink,
+            Engine,
+        )>();
     }
 }
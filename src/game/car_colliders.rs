@@ -5,7 +5,7 @@ use bevy::{prelude::*, render::mesh::MeshAabb};
 
 use crate::screens::Screen;
 
-use super::{car::CarAssets, consts::WHEELFRICTIONNORMAL};
+use super::{car::CarAssets, layers::GameLayer};
 
 pub fn plugin(app: &mut App) {
     app.register_type::<WheelCollider>();
@@ -50,39 +50,43 @@ pub struct CarColliders {
 }
 
 impl CarColliders {
-    pub fn get_wheel_bl_bundle(&self) -> impl Bundle {
+    pub fn get_wheel_bl_bundle(&self, friction: f32) -> impl Bundle {
         (
             Name::new("wheel-bl-collider"),
             self.wheel_bl.clone(),
             self.transfrom_bl,
-            Friction::new(WHEELFRICTIONNORMAL),
+            Friction::new(friction),
+            GameLayer::Wheel.collision_layers(),
             WheelCollider,
         )
     }
-    pub fn get_wheel_br_bundle(&self) -> impl Bundle {
+    pub fn get_wheel_br_bundle(&self, friction: f32) -> impl Bundle {
         (
             Name::new("wheel-br-collider"),
             self.wheel_br.clone(),
             self.transfrom_br,
-            Friction::new(WHEELFRICTIONNORMAL),
+            Friction::new(friction),
+            GameLayer::Wheel.collision_layers(),
             WheelCollider,
         )
     }
-    pub fn get_wheel_fl_bundle(&self) -> impl Bundle {
+    pub fn get_wheel_fl_bundle(&self, friction: f32) -> impl Bundle {
         (
             Name::new("wheel-fl-collider"),
             self.wheel_fl.clone(),
             self.transfrom_fl,
-            Friction::new(WHEELFRICTIONNORMAL),
+            Friction::new(friction),
+            GameLayer::Wheel.collision_layers(),
             WheelCollider,
         )
     }
-    pub fn get_wheel_fr_bundle(&self) -> impl Bundle {
+    pub fn get_wheel_fr_bundle(&self, friction: f32) -> impl Bundle {
         (
             Name::new("wheel-fr-collider"),
             self.wheel_fr.clone(),
             self.transfrom_fr,
-            Friction::new(WHEELFRICTIONNORMAL),
+            Friction::new(friction),
+            GameLayer::Wheel.collision_layers(),
             WheelCollider,
         )
     }
@@ -6,7 +6,10 @@ use crate::{
     AppSystems, PausableSystems,
     game::{
         car::spawn_car,
-        consts::{DISTANCEUNTILCARSREACHTHEROAD, MAX_AMOUNT_OF_CARS, ROADLENGTH},
+        consts::MAX_AMOUNT_OF_CARS,
+        road_network::{PathFollower, RoadNetwork},
+        round::GameRng,
+        tuning::GameTuning,
     },
     screens::Screen,
 };
@@ -17,6 +20,11 @@ use super::{
     consts::{MAXCARHEIGHT, MAXCARLENGTH, MAXCARWIDTH},
 };
 
+/// Distance from the road network beyond which cars are despawned.
+const DESPAWN_MARGIN: f32 = 10.;
+/// Shortest time in seconds between two cars of a spawner.
+const MIN_SPAWN_INTERVAL: f32 = 0.1;
+
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<CarSpawner>();
 
@@ -29,44 +37,68 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-/// The car spawner is located `DISTANCEUNTILCARSREACHTHEROAD` units away from the beginning of the road.
-///
-/// It spawns cars, which accelerate towards the road.
+/// The car spawner is located at the start of a path, `DISTANCEUNTILCARSREACHTHEROAD` units away
+/// from the beginning of the road for straight lanes.
 ///
-/// IT ASSUMES THE LANES ARE ONLY IN +X OR -X DIRECTION!
+/// It spawns cars, which accelerate along the path towards the road.
 #[derive(Debug, Default, Component, Reflect)]
 pub struct CarSpawner {
+    path: usize,
     target_velocity: f32,
-    driving_direction: Vec3, // This has to be a normalized vector!
+    min_spawn_interval: f32,
+    max_spawn_interval: f32,
     timer: Timer,
 }
 
 impl CarSpawner {
-    pub fn new(target_velocity: f32, driving_direction: Vec3) -> Self {
+    /// Creates a spawner, fixing spawn intervals of a hand-written level which are out of order
+    /// or not positive.
+    pub fn new(
+        path: usize,
+        target_velocity: f32,
+        min_spawn_interval: f32,
+        max_spawn_interval: f32,
+    ) -> Self {
+        if min_spawn_interval > max_spawn_interval {
+            warn!(
+                "The spawner on path {path} has a minimal spawn interval of {min_spawn_interval} \
+                above its maximal one of {max_spawn_interval}, swapping them"
+            );
+        }
+        let (min_spawn_interval, max_spawn_interval) = (
+            min_spawn_interval.min(max_spawn_interval),
+            min_spawn_interval.max(max_spawn_interval),
+        );
+        if min_spawn_interval < MIN_SPAWN_INTERVAL {
+            warn!(
+                "The spawner on path {path} has a spawn interval below {MIN_SPAWN_INTERVAL} \
+                seconds, raising it"
+            );
+        }
+        let min_spawn_interval = min_spawn_interval.max(MIN_SPAWN_INTERVAL);
+        let max_spawn_interval = max_spawn_interval.max(MIN_SPAWN_INTERVAL);
+
         CarSpawner {
+            path,
             target_velocity,
-            driving_direction,
+            min_spawn_interval,
+            max_spawn_interval,
             timer: Timer::default(),
         }
     }
+
+    /// Index of the path in the [`RoadNetwork`] the cars are spawned on.
+    pub fn path(&self) -> usize {
+        self.path
+    }
 }
 
 /// Returns a `Bundle` representing a car spawner entity.
-pub fn create_car_spawner(
-    mid_of_lane_coord_z: f32,
-    driving_direction: Vec3,
-    target_velocity: f32,
-) -> impl Bundle {
-    assert!(driving_direction == Vec3::X || driving_direction == Vec3::NEG_X);
-
+pub fn create_car_spawner(position: Vec3, spawner: CarSpawner) -> impl Bundle {
     (
         Name::new("CarSpawner"),
-        Transform::from_xyz(
-            -driving_direction.x * (ROADLENGTH / 2. + DISTANCEUNTILCARSREACHTHEROAD),
-            MAXCARHEIGHT / 2.,
-            mid_of_lane_coord_z,
-        ),
-        CarSpawner::new(target_velocity, driving_direction),
+        Transform::from_translation(position),
+        spawner,
         StateScoped(Screen::Gameplay),
     )
 }
@@ -79,21 +111,33 @@ fn update_car_spawners(
     spatial_query: SpatialQuery,
     car_assets: Res<CarAssets>,
     all_car_colliders: Res<AllCarColliders>,
+    tuning: Res<GameTuning>,
+    network: Res<RoadNetwork>,
     cars: Query<Entity, With<Car>>,
     time: Res<Time>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let rng = &mut rand::thread_rng();
-
     if cars.iter().len() >= MAX_AMOUNT_OF_CARS {
         return;
     }
 
     for (mut spawner, transform) in &mut spawners {
         if spawner.timer.tick(time.delta()).finished() {
+            let Some(path) = network.paths.get(spawner.path) else {
+                continue;
+            };
+            let driving_direction = path.direction_at(0.);
+
             // Check, if there is space to spawn the car
             let spawn_area = ColliderAabb::new(
                 transform.translation,
-                Vec3::new(MAXCARLENGTH, MAXCARHEIGHT, MAXCARWIDTH) / 2.,
+                Vec3::new(
+                    driving_direction.x.abs() * MAXCARLENGTH
+                        + driving_direction.z.abs() * MAXCARWIDTH,
+                    MAXCARHEIGHT,
+                    driving_direction.z.abs() * MAXCARLENGTH
+                        + driving_direction.x.abs() * MAXCARWIDTH,
+                ) / 2.,
             );
 
             let colliders_in_spawn = spatial_query.aabb_intersections_with_aabb(spawn_area);
@@ -116,24 +160,32 @@ fn update_car_spawners(
                 &mut entity_commands,
                 &car_assets,
                 &all_car_colliders,
+                &tuning,
+                &mut rng,
                 transform.translation.with_y(0.01),
                 spawner.target_velocity,
-                spawner.driving_direction,
+                driving_direction,
             );
+            entity_commands.insert(PathFollower::new(spawner.path));
 
             /* Reset Timer */
-            spawner.timer = Timer::from_seconds(rng.gen_range(2.0..5.0), TimerMode::Once);
+            let interval = rng.gen_range(spawner.min_spawn_interval..=spawner.max_spawn_interval);
+            spawner.timer = Timer::from_seconds(interval, TimerMode::Once);
         }
     }
 }
 
-// System for despawning cars that are outside of the visible area.
-fn despawn_cars(mut commands: Commands, cars: Query<(Entity, &Transform), With<Car>>) {
+/// Despawns cars which were thrown off the road network, or fell off the world.
+///
+/// Cars following a path are despawned when they reach its end, see `follow_paths`.
+fn despawn_cars(
+    mut commands: Commands,
+    cars: Query<(Entity, &Transform), With<Car>>,
+    network: Res<RoadNetwork>,
+) {
+    let bounds = network.bounds().inflate(DESPAWN_MARGIN);
     for (entity, transform) in cars.iter() {
-        if transform.translation.xz().length()
-            > (ROADLENGTH / 2. + DISTANCEUNTILCARSREACHTHEROAD * 1.5)
-            || transform.translation.y < -10.
-        {
+        if !bounds.contains(transform.translation.xz()) || transform.translation.y < -10. {
             commands.entity(entity).despawn();
         }
     }
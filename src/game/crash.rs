@@ -0,0 +1,257 @@
+//! Detection of crashes between cars and everything they can crash into.
+//!
+//! The severity of a crash is the impulse a perfectly inelastic impact would exchange along the
+//! contact normal: the reduced mass of both bodies times their relative velocity towards each
+//! other. It only depends on the bodies' masses, velocities and the impact angle, not on the
+//! timestep or the contact solver.
+
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{
+    car::Car, pertubator::Pertubator, road::Road, scenery::SceneryProp, tuning::GameTuning,
+};
+
+/// Crashes of the same pair of bodies within this time count as one crash.
+const CRASH_COOLDOWN: Duration = Duration::from_millis(500);
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CarCrashable>();
+    app.register_type::<PreStepVelocity>();
+    app.register_type::<CarCrash>();
+    app.add_event::<CarCrash>();
+    app.register_type::<CarLanded>();
+    app.add_event::<CarLanded>();
+    app.init_resource::<RecentCrashes>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), reset_recent_crashes);
+    // The collisions of the last physics step are turned into crashes before the game reacts to
+    // them in `AppSystems::Update`.
+    app.add_systems(
+        FixedUpdate,
+        detect_crashes
+            .in_set(AppSystems::RecordInput)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        FixedPostUpdate,
+        record_pre_step_velocities.before(PhysicsSet::StepSimulation),
+    );
+}
+
+/// Marks bodies cars can crash into.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct CarCrashable;
+
+/// The velocity of a body before the current physics step.
+///
+/// When a crash is detected, the bodies already bounced off each other, so the impact is
+/// calculated from this velocity.
+#[derive(Debug, Default, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct PreStepVelocity(pub Vec3);
+
+/// What a car crashed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum CrashKind {
+    Car,
+    Separator,
+    Pertubator,
+    /// Trees, fences and everything else around the road.
+    Scenery,
+}
+
+/// A crash of a car. Written once per pair of crashing bodies.
+#[derive(Debug, Event, Reflect)]
+pub struct CarCrash {
+    pub car: Entity,
+    /// The other body, which is a car as well for [`CrashKind::Car`].
+    pub other: Entity,
+    pub kind: CrashKind,
+    /// Impulse exchanged along the contact normal in Ns.
+    pub severity: f32,
+}
+
+/// A car hitting the ground hard, e.g. after a spring or a bounce. Not a [`CarCrash`], so it only
+/// makes a sound.
+#[derive(Debug, Event, Reflect)]
+pub struct CarLanded {
+    pub car: Entity,
+    /// The severity of the impact, see the module documentation.
+    pub severity: f32,
+}
+
+/// Time of the last crash of each pair of bodies, ordered by entity.
+#[derive(Debug, Default, Resource)]
+struct RecentCrashes(HashMap<(Entity, Entity), Duration>);
+
+fn reset_recent_crashes(mut recent_crashes: ResMut<RecentCrashes>) {
+    recent_crashes.0.clear();
+}
+
+fn record_pre_step_velocities(mut bodies: Query<(&LinearVelocity, &mut PreStepVelocity)>) {
+    for (velocity, mut pre_step_velocity) in &mut bodies {
+        pre_step_velocity.0 = velocity.0;
+    }
+}
+
+fn detect_crashes(
+    mut collisions_started: EventReader<CollisionStarted>,
+    mut car_crash_writer: EventWriter<CarCrash>,
+    mut car_landed_writer: EventWriter<CarLanded>,
+    mut recent_crashes: ResMut<RecentCrashes>,
+    colliders: Query<&ColliderOf, Without<Sensor>>,
+    bodies: Query<CrashBody, With<CarCrashable>>,
+    kinds: Query<(Has<Car>, Has<Pertubator>, Has<Road>, Has<SceneryProp>)>,
+    collisions: Collisions,
+    tuning: Res<GameTuning>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    recent_crashes
+        .0
+        .retain(|_, crash_time| now.saturating_sub(*crash_time) < CRASH_COOLDOWN);
+
+    for CollisionStarted(collider1, collider2) in collisions_started.read() {
+        let (Ok(body1), Ok(body2)) = (colliders.get(*collider1), colliders.get(*collider2)) else {
+            continue;
+        };
+        let (body1, body2) = (body1.body, body2.body);
+
+        // One car is required, and both bodies have to be crashable.
+        let (car, other) = if kinds.get(body1).is_ok_and(|(is_car, ..)| is_car) {
+            (body1, body2)
+        } else if kinds.get(body2).is_ok_and(|(is_car, ..)| is_car) {
+            (body2, body1)
+        } else {
+            continue;
+        };
+        let (Ok(car_body), Ok(other_body)) = (bodies.get(car), bodies.get(other)) else {
+            continue;
+        };
+
+        // Several colliders of the same bodies, e.g. the wheels, may start touching at once.
+        let pair = if car < other {
+            (car, other)
+        } else {
+            (other, car)
+        };
+        if recent_crashes.0.contains_key(&pair) {
+            continue;
+        }
+
+        let relative_velocity = pre_step_velocity(car_body) - pre_step_velocity(other_body);
+        let normal = collisions
+            .get(*collider1, *collider2)
+            .and_then(|contact_pair| contact_pair.manifolds.first())
+            .map(|manifold| manifold.normal);
+
+        let inverse_mass_sum = inverse_mass(car_body) + inverse_mass(other_body);
+        if inverse_mass_sum <= 0. {
+            continue;
+        }
+        let severity = severity(relative_velocity, normal, inverse_mass_sum);
+        if severity < tuning.minimal_crash_severity {
+            continue;
+        }
+
+        // Anything else crashable a car hits is the ground.
+        let kind = match kinds.get(other) {
+            Ok((true, ..)) => Some(CrashKind::Car),
+            Ok((_, true, ..)) => Some(CrashKind::Pertubator),
+            Ok((_, _, true, _)) => Some(CrashKind::Separator),
+            Ok((.., true)) => Some(CrashKind::Scenery),
+            _ => None,
+        };
+
+        recent_crashes.0.insert(pair, now);
+        if let Some(kind) = kind {
+            car_crash_writer.write(CarCrash {
+                car,
+                other,
+                kind,
+                severity,
+            });
+        } else {
+            car_landed_writer.write(CarLanded { car, severity });
+        }
+    }
+}
+
+/// The severity of a crash, see the module documentation. Without a contact normal, the bodies
+/// are assumed to hit head-on.
+fn severity(relative_velocity: Vec3, normal: Option<Vec3>, inverse_mass_sum: f32) -> f32 {
+    let normal_speed = normal.map_or(relative_velocity.length(), |normal| {
+        relative_velocity.dot(normal).abs()
+    });
+    normal_speed / inverse_mass_sum
+}
+
+/// The data of a body needed to calculate the severity of a crash.
+type CrashBody<'a> = (
+    &'a RigidBody,
+    &'a ComputedMass,
+    Option<&'a PreStepVelocity>,
+    Option<&'a LinearVelocity>,
+);
+
+fn pre_step_velocity((rigid_body, _, pre_step_velocity, velocity): CrashBody<'_>) -> Vec3 {
+    if rigid_body.is_static() {
+        return Vec3::ZERO;
+    }
+
+    pre_step_velocity
+        .map(|velocity| velocity.0)
+        .or(velocity.map(|velocity| velocity.0))
+        .unwrap_or_default()
+}
+
+/// Static and kinematic bodies are not moved by a crash, so their mass is infinite.
+fn inverse_mass((rigid_body, mass, ..): CrashBody<'_>) -> f32 {
+    if rigid_body.is_dynamic() {
+        mass.inverse()
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-5,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn severity_scales_with_mass() {
+        let velocity = Vec3::new(10., 0., 0.);
+        let light = severity(velocity, Some(Vec3::X), 1. / 1000.);
+        let heavy = severity(velocity, Some(Vec3::X), 1. / 2000.);
+        assert_close(light, 10_000.);
+        assert_close(heavy, 2. * light);
+    }
+
+    #[test]
+    fn severity_scales_with_impact_angle() {
+        let velocity = Vec3::new(10., 0., 0.);
+        let head_on = severity(velocity, Some(Vec3::X), 1.);
+        let glancing = severity(velocity, Some(Vec3::new(1., 0., 1.).normalize()), 1.);
+        assert_close(head_on, 10.);
+        assert_close(glancing, head_on * std::f32::consts::FRAC_1_SQRT_2);
+        assert_eq!(severity(velocity, Some(Vec3::Z), 1.), 0.);
+        // The direction of the normal does not matter.
+        assert_close(severity(velocity, Some(Vec3::NEG_X), 1.), head_on);
+        // Without a normal, the bodies hit head-on.
+        assert_close(severity(velocity, None, 1.), head_on);
+    }
+}
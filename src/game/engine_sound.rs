@@ -0,0 +1,192 @@
+//! The sound of the cars on the road.
+//!
+//! Engines rev up with the speed of their car, in the voice of its archetype. Only the engines
+//! closest to the center of the view play, so the traffic doesn't drown in its own noise. Cars on
+//! soap screech while they slide.
+
+use avian3d::prelude::*;
+use bevy::{audio::Volume, platform::collections::HashSet, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    audio::{Engine, SoundEffect},
+    camera::CameraRig,
+    screens::Screen,
+};
+
+use super::{
+    car::{Car, CarAssets},
+    car_colliders::WheelCollider,
+    pertubator::Soaped,
+    tuning::GameTuning,
+};
+
+/// Volume of an idling engine, relative to one at full revs.
+const IDLE_VOLUME: f32 = 0.5;
+/// Playback speed of a tire screech at the slowest and the fastest slide.
+const SCREECH_PITCH: (f32, f32) = (0.8, 1.2);
+/// Smallest changes of the speed and of the volume in decibels of a sound which are applied.
+/// Every change updates the sink, so the small jitter of the velocities is ignored.
+const SPEED_TOLERANCE: f32 = 0.01;
+const VOLUME_TOLERANCE: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<EngineArchetype>();
+    app.register_type::<TireScreech>();
+
+    app.add_systems(
+        Update,
+        (
+            rev_engines,
+            limit_engine_voices,
+            start_tire_screech,
+            screech_tires,
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The kind of engine a car model has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub enum EngineArchetype {
+    Compact,
+    Sports,
+    Van,
+    Heavy,
+    Tractor,
+}
+
+impl EngineArchetype {
+    /// Playback speed of the engine loop when idling and at full revs.
+    fn pitch(self) -> (f32, f32) {
+        match self {
+            Self::Compact => (0.5, 1.1),
+            Self::Sports => (0.7, 1.6),
+            Self::Van => (0.4, 0.9),
+            Self::Heavy => (0.25, 0.6),
+            Self::Tractor => (0.2, 0.5),
+        }
+    }
+
+    /// Volume of the engine at full revs.
+    fn volume(self) -> Volume {
+        Volume::Decibels(match self {
+            Self::Compact | Self::Van => -24.,
+            Self::Sports => -21.,
+            Self::Heavy => -20.,
+            Self::Tractor => -22.,
+        })
+    }
+}
+
+/// The looping screech of a car sliding on soap.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+struct TireScreech;
+
+/// Sets the pitch and the volume of the engines according to the speed of their cars.
+fn rev_engines(
+    mut engines: Query<(&EngineArchetype, &LinearVelocity, &mut PlaybackSettings), With<Engine>>,
+    tuning: Res<GameTuning>,
+) {
+    for (archetype, velocity, mut playback) in &mut engines {
+        let revs = (velocity.length() / tuning.engine_full_revs_speed).min(1.);
+        let (idle_pitch, full_pitch) = archetype.pitch();
+        let speed = idle_pitch.lerp(full_pitch, revs);
+        let volume = archetype.volume() * Volume::Linear(IDLE_VOLUME.lerp(1., revs));
+        set_playback(&mut playback, speed, volume);
+    }
+}
+
+/// Sets the speed and the volume of a sound, if they changed noticeably.
+fn set_playback(playback: &mut Mut<PlaybackSettings>, speed: f32, volume: Volume) {
+    let speed_changed = (playback.speed - speed).abs() > SPEED_TOLERANCE;
+    let volume_changed =
+        (playback.volume.to_decibels() - volume.to_decibels()).abs() > VOLUME_TOLERANCE;
+    if speed_changed || volume_changed {
+        playback.speed = speed;
+        playback.volume = volume;
+    }
+}
+
+/// Only plays the engines closest to the center of the view.
+fn limit_engine_voices(
+    engines: Query<(&GlobalTransform, &SpatialAudioSink), With<Engine>>,
+    rig: Single<&CameraRig>,
+    tuning: Res<GameTuning>,
+) {
+    let mut engines = engines
+        .iter()
+        .map(|(transform, sink)| {
+            let position = transform.translation().xz();
+            (position.distance_squared(rig.focus), sink)
+        })
+        .collect::<Vec<_>>();
+    engines.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    for (index, (_, sink)) in engines.into_iter().enumerate() {
+        let audible = index < tuning.engine_voices as usize;
+        if audible && sink.is_paused() {
+            sink.play();
+        } else if !audible && !sink.is_paused() {
+            sink.pause();
+        }
+    }
+}
+
+/// Gives a car its tire screech when one of its wheels gets soaped.
+fn start_tire_screech(
+    mut commands: Commands,
+    soaped_wheels: Query<&ChildOf, (Added<Soaped>, With<WheelCollider>)>,
+    cars: Query<Option<&Children>, With<Car>>,
+    screeches: Query<(), With<TireScreech>>,
+    car_assets: Res<CarAssets>,
+) {
+    let mut screeching = HashSet::default();
+    for child_of in &soaped_wheels {
+        let car = child_of.parent();
+        let Ok(children) = cars.get(car) else {
+            continue;
+        };
+        let has_screech = children
+            .into_iter()
+            .flatten()
+            .any(|child| screeches.contains(*child));
+        if has_screech || !screeching.insert(car) {
+            continue;
+        }
+        commands.spawn((
+            Name::new("Tire Screech"),
+            ChildOf(car),
+            Transform::default(),
+            AudioPlayer(car_assets.tire_screech_audio.clone()),
+            PlaybackSettings::LOOP
+                .with_volume(Volume::SILENT)
+                .with_spatial(true),
+            SoundEffect,
+            TireScreech,
+        ));
+    }
+}
+
+/// Screeches louder and higher the faster a car slides, and falls silent when it stands still.
+fn screech_tires(
+    mut screeches: Query<(&ChildOf, &mut PlaybackSettings), With<TireScreech>>,
+    cars: Query<&LinearVelocity>,
+    tuning: Res<GameTuning>,
+) {
+    for (child_of, mut playback) in &mut screeches {
+        let Ok(velocity) = cars.get(child_of.parent()) else {
+            continue;
+        };
+        let slide = ((velocity.length() - tuning.screech_min_speed)
+            / (tuning.engine_full_revs_speed - tuning.screech_min_speed))
+            .clamp(0., 1.);
+        let (slow_pitch, fast_pitch) = SCREECH_PITCH;
+        let speed = slow_pitch.lerp(fast_pitch, slide);
+        set_playback(&mut playback, speed, Volume::Linear(slide));
+    }
+}
@@ -0,0 +1,242 @@
+//! Generates straight highways for endless play.
+//!
+//! A highway is made of direction groups: lanes driving the same way, framed by border tiles.
+//! Groups and the road edges may be separated by barriers. The same seed and parameters always
+//! produce the same highway.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use super::{
+    consts::LANEWIDTH,
+    level::{
+        LaneDefinition, LaneDirection, LevelDefinition, RoadTile, SceneryDefinition, default_music,
+    },
+    road_network::RoadNetworkDefinition,
+};
+
+/// Shapes the generated highways.
+#[derive(Debug, Clone)]
+pub struct HighwayParameters {
+    /// From 0 (easy) to 1 (hard). More lanes and denser, faster traffic make crashes easier, but
+    /// the player has to keep up with them.
+    pub difficulty: f32,
+    /// Upper bound of the lanes driving the same way.
+    pub max_lanes_per_direction: u32,
+    /// Bounds of the road length, rounded to whole tiles.
+    pub min_road_length: f32,
+    pub max_road_length: f32,
+    /// Chance of a barrier between two direction groups and at each edge of the road.
+    pub separator_chance: f64,
+    /// From 0 (bare) to 1 (the most grass).
+    pub scenery_density: f32,
+}
+
+impl Default for HighwayParameters {
+    fn default() -> Self {
+        Self {
+            difficulty: 0.,
+            max_lanes_per_direction: 4,
+            min_road_length: 88.,
+            max_road_length: 120.,
+            separator_chance: 0.6,
+            scenery_density: 0.8,
+        }
+    }
+}
+
+/// Rows of grass at full scenery density.
+const MAX_GRASS_ROWS: f32 = 30.;
+
+/// Generates a highway from `seed`.
+pub fn generate_highway(seed: u64, parameters: &HighwayParameters) -> LevelDefinition {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let difficulty = parameters.difficulty.clamp(0., 1.);
+
+    // Harder highways have more lanes, but never less than one in each group.
+    let max_lanes = parameters.max_lanes_per_direction.max(1);
+    let min_lanes = (1. + difficulty * (max_lanes - 1) as f32).floor() as u32;
+    let lanes_per_group = |rng: &mut StdRng| rng.gen_range(min_lanes..=max_lanes);
+
+    let groups = if rng.gen_bool(0.8) {
+        vec![
+            (LaneDirection::LeftToRight, lanes_per_group(&mut rng)),
+            (LaneDirection::RightToLeft, lanes_per_group(&mut rng)),
+        ]
+    } else {
+        let direction = if rng.r#gen() {
+            LaneDirection::LeftToRight
+        } else {
+            LaneDirection::RightToLeft
+        };
+        vec![(direction, lanes_per_group(&mut rng))]
+    };
+
+    // Cars drive on the edges between the tiles: A group of `n` lanes spans `n + 1` tiles.
+    let mut tiles = vec![];
+    let mut lanes = vec![];
+    for (direction, lane_count) in groups {
+        // A barrier at the edge of the road before the first group, and between the groups.
+        if rng.gen_bool(parameters.separator_chance) {
+            tiles.push(RoadTile::Separator);
+        }
+
+        let group_start = tiles.len();
+        tiles.push(RoadTile::Border);
+        tiles.extend((1..lane_count).map(|_| RoadTile::Straight));
+        tiles.push(RoadTile::Border);
+
+        for lane in 1..=lane_count {
+            lanes.push(lane_definition(
+                (group_start as u32 + lane) as f32,
+                direction,
+                difficulty,
+                &mut rng,
+            ));
+        }
+    }
+    if rng.gen_bool(parameters.separator_chance) {
+        tiles.push(RoadTile::Separator);
+    }
+
+    let tile_count = rng.gen_range(
+        (parameters.min_road_length / LANEWIDTH).round() as u32
+            ..=(parameters.max_road_length / LANEWIDTH).round() as u32,
+    );
+    let grass_rows = MAX_GRASS_ROWS * parameters.scenery_density.clamp(0., 1.);
+
+    LevelDefinition {
+        name: format!("Highway {seed:016x}"),
+        road_length: tile_count as f32 * LANEWIDTH,
+        tiles,
+        lanes,
+        network: RoadNetworkDefinition::default(),
+        scenery: SceneryDefinition {
+            grass_rows: rng.gen_range(grass_rows * 0.5..=grass_rows).round() as u32,
+            density: parameters.scenery_density,
+            ..Default::default()
+        },
+        allowed_pertubators: vec![],
+        restricted_zones: vec![],
+        surfaces: vec![],
+        description: "A new highway every round.".into(),
+        unlock_score: 0.,
+        music: default_music(),
+        music_layers: vec![],
+        music_stinger: None,
+    }
+}
+
+fn lane_definition(
+    position: f32,
+    direction: LaneDirection,
+    difficulty: f32,
+    rng: &mut StdRng,
+) -> LaneDefinition {
+    let target_velocity = 4. + 3. * difficulty + rng.gen_range(0.0..1.0);
+    let min_spawn_interval = 3. - 1.8 * difficulty + rng.gen_range(0.0..0.5);
+    let max_spawn_interval = min_spawn_interval + 3. - 1.5 * difficulty;
+
+    LaneDefinition {
+        position,
+        direction,
+        target_velocity,
+        min_spawn_interval,
+        max_spawn_interval,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> impl Iterator<Item = HighwayParameters> {
+        [0., 0.5, 1.]
+            .into_iter()
+            .map(|difficulty| HighwayParameters {
+                difficulty,
+                ..Default::default()
+            })
+    }
+
+    #[test]
+    fn road_length_is_within_bounds() {
+        for parameters in parameters() {
+            for seed in 0..20 {
+                let highway = generate_highway(seed, &parameters);
+                // The bounds are rounded to whole tiles.
+                let min = parameters.min_road_length - LANEWIDTH / 2.;
+                let max = parameters.max_road_length + LANEWIDTH / 2.;
+                assert!(
+                    (min..=max).contains(&highway.road_length),
+                    "road length {} of seed {seed}",
+                    highway.road_length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn road_is_framed_by_borders() {
+        for parameters in parameters() {
+            for seed in 0..20 {
+                let highway = generate_highway(seed, &parameters);
+                // Only barriers may lie outside the outer borders.
+                let mut tiles = highway
+                    .tiles
+                    .iter()
+                    .filter(|tile| **tile != RoadTile::Separator);
+                assert_eq!(tiles.next(), Some(&RoadTile::Border), "seed {seed}");
+                assert_eq!(tiles.next_back(), Some(&RoadTile::Border), "seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn lanes_are_on_the_road() {
+        for parameters in parameters() {
+            for seed in 0..20 {
+                let highway = generate_highway(seed, &parameters);
+                assert!(!highway.lanes.is_empty(), "seed {seed}");
+                for lane in &highway.lanes {
+                    assert!(
+                        lane.position > 0. && lane.position < highway.tiles.len() as f32,
+                        "lane at {} of seed {seed}",
+                        lane.position
+                    );
+                    // Cars drive on the edge between two tiles, neither of them a barrier.
+                    let edge = lane.position as usize;
+                    assert!(
+                        highway.tiles[edge - 1..=edge]
+                            .iter()
+                            .all(|tile| *tile != RoadTile::Separator),
+                        "lane at {} of seed {seed} touches a barrier",
+                        lane.position
+                    );
+                    assert!(
+                        lane.min_spawn_interval <= lane.max_spawn_interval,
+                        "spawn intervals of seed {seed}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_highway() {
+        for parameters in parameters() {
+            for seed in 0..20 {
+                assert_eq!(
+                    generate_highway(seed, &parameters),
+                    generate_highway(seed, &parameters)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_give_different_highways() {
+        let parameters = HighwayParameters::default();
+        let first = generate_highway(0, &parameters);
+        assert!((1..20).any(|seed| generate_highway(seed, &parameters) != first));
+    }
+}
@@ -0,0 +1,67 @@
+//! Collision layers of everything physical in the game.
+//!
+//! Bodies only collide, and sensors only detect, what can matter for them. This also keeps the
+//! broadphase from checking pairs like grass and nails.
+
+use avian3d::prelude::*;
+
+#[derive(PhysicsLayer, Default, Debug, Clone, Copy)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    /// The road everything drives and falls on.
+    Ground,
+    /// The body of a car.
+    Car,
+    Wheel,
+    /// Small parts flying off a crash.
+    Debris,
+    /// Solid pertubators, like the spring.
+    Pertubator,
+    /// Sensors triggering a pertubator.
+    PertubatorSensor,
+    /// Road separators and other barriers.
+    Barrier,
+    /// Decorations around the road.
+    Scenery,
+}
+
+impl GameLayer {
+    /// The layers of a solid collider of this layer.
+    ///
+    /// Sensors of pertubators declare their own filters with [`Self::pertubator_sensor`].
+    pub fn collision_layers(self) -> CollisionLayers {
+        let filters = match self {
+            GameLayer::Default => LayerMask::ALL,
+            GameLayer::Ground => [
+                GameLayer::Car,
+                GameLayer::Wheel,
+                GameLayer::Debris,
+                GameLayer::Scenery,
+            ]
+            .into(),
+            GameLayer::Car | GameLayer::Wheel => [
+                GameLayer::Ground,
+                GameLayer::Car,
+                GameLayer::Wheel,
+                GameLayer::Pertubator,
+                GameLayer::PertubatorSensor,
+                GameLayer::Barrier,
+                GameLayer::Scenery,
+            ]
+            .into(),
+            GameLayer::Debris => [GameLayer::Ground, GameLayer::Barrier].into(),
+            GameLayer::Pertubator => [GameLayer::Car, GameLayer::Wheel].into(),
+            GameLayer::PertubatorSensor => LayerMask::NONE,
+            GameLayer::Barrier => [GameLayer::Car, GameLayer::Wheel, GameLayer::Debris].into(),
+            GameLayer::Scenery => [GameLayer::Ground, GameLayer::Car, GameLayer::Wheel].into(),
+        };
+
+        CollisionLayers::new(self, filters)
+    }
+
+    /// The layers of a pertubator sensor, triggered by the given layers.
+    pub fn pertubator_sensor(triggered_by: impl Into<LayerMask>) -> CollisionLayers {
+        CollisionLayers::new(GameLayer::PertubatorSensor, triggered_by)
+    }
+}
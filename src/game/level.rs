@@ -0,0 +1,533 @@
+//! Level definitions, loaded from `assets/levels/*.level.ron`.
+//!
+//! A level describes the road, the paths cars drive and spawn on, the scenery around the road and
+//! the pertubators allowed on it. The road, the car spawners and the grass are built from it.
+//!
+//! All levels of [`LEVELS`] are loaded up front. Entering [`Screen::Loading`] inserts the
+//! [`SelectedLevel`] as the [`LevelDefinition`] resource and loads its music. In endless play,
+//! a new highway is generated from the seed of each round instead.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_tracking::{LoadResource, RonAssetLoader},
+    screens::Screen,
+};
+
+use super::{
+    consts::LANEWIDTH,
+    highway_generator::{HighwayParameters, generate_highway},
+    pertubator::Pertubator,
+    points_money::HighScore,
+    replay::ReplayPlayback,
+    road_network::RoadNetworkDefinition,
+    round::{RoundSeed, start_round},
+};
+
+/// The levels in the order they are unlocked.
+pub const LEVELS: [&str; 3] = [
+    "levels/highway.level.ron",
+    "levels/junction.level.ron",
+    "levels/interchange.level.ron",
+];
+
+/// Stands in for the path of the generated highways of endless play.
+const ENDLESS: &str = "endless";
+
+/// Score at which the endless highways reach their highest difficulty.
+const ENDLESS_MAX_DIFFICULTY_SCORE: f32 = 20000.;
+
+#[cfg(not(target_family = "wasm"))]
+const RECORDS_FILE: &str = "records.ron";
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelDefinition>();
+    app.register_type::<SelectedLevel>();
+    app.register_type::<LevelAssets>();
+    app.init_asset::<LevelDefinition>();
+    app.register_asset_loader(RonAssetLoader::<LevelDefinition>::new("level.ron"));
+    app.load_resource::<LevelAssets>();
+    app.init_resource::<SelectedLevel>();
+    app.insert_resource(LevelRecords::load());
+
+    app.add_systems(OnEnter(Screen::Loading), load_selected_level);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        generate_endless_highway
+            .after(start_round)
+            .run_if(resource_equals(SelectedLevel::Endless)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), record_best_score);
+    app.add_systems(Update, reload_selected_level);
+}
+
+/// Handles of all [`LEVELS`].
+#[derive(Debug, Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LevelAssets {
+    #[dependency]
+    pub levels: Vec<Handle<LevelDefinition>>,
+}
+
+impl FromWorld for LevelAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            levels: LEVELS.iter().map(|path| assets.load(*path)).collect(),
+        }
+    }
+}
+
+/// The level played next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource, Reflect)]
+#[reflect(Resource)]
+pub enum SelectedLevel {
+    /// Index into [`LEVELS`].
+    Level(usize),
+    /// A generated highway, new in every round.
+    Endless,
+}
+
+impl Default for SelectedLevel {
+    fn default() -> Self {
+        Self::Level(0)
+    }
+}
+
+impl SelectedLevel {
+    pub fn from_path(path: &str) -> Option<Self> {
+        if path == ENDLESS {
+            return Some(Self::Endless);
+        }
+        LEVELS
+            .iter()
+            .position(|level| *level == path)
+            .map(Self::Level)
+    }
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            SelectedLevel::Level(index) => LEVELS[*index],
+            SelectedLevel::Endless => ENDLESS,
+        }
+    }
+}
+
+/// The music of the current level, loaded on the loading screen.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct LevelMusic {
+    pub track: Handle<AudioSource>,
+    /// Intensity layers, with the chaos at which they are fully audible.
+    pub layers: Vec<(Handle<AudioSource>, f32)>,
+    /// Played on huge chain reactions.
+    pub stinger: Option<Handle<AudioSource>>,
+}
+
+impl LevelMusic {
+    fn load(asset_server: &AssetServer, level: &LevelDefinition) -> Self {
+        Self {
+            track: asset_server.load(&level.music),
+            layers: level
+                .music_layers
+                .iter()
+                .map(|layer| (asset_server.load(&layer.path), layer.threshold))
+                .collect(),
+            stinger: level
+                .music_stinger
+                .as_ref()
+                .map(|path| asset_server.load(path)),
+        }
+    }
+
+    pub fn is_loaded(&self, asset_server: &AssetServer) -> bool {
+        std::iter::once(&self.track)
+            .chain(self.layers.iter().map(|(layer, _)| layer))
+            .chain(&self.stinger)
+            .all(|handle| asset_server.is_loaded_with_dependencies(handle))
+    }
+}
+
+/// Present while the level is play-tested in the level editor. Play-tests set no records.
+#[derive(Debug, Default, Resource)]
+pub struct PlayTest;
+
+/// The best score reached on each level, by path. Saved on native builds.
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+pub struct LevelRecords {
+    best_scores: BTreeMap<String, f32>,
+}
+
+impl LevelRecords {
+    pub fn best_score(&self, level: SelectedLevel) -> Option<f32> {
+        self.best_scores.get(level.path()).copied()
+    }
+
+    /// A level is unlocked by reaching its `unlock_score` on the level before it.
+    pub fn is_unlocked(&self, level: SelectedLevel, definition: &LevelDefinition) -> bool {
+        let SelectedLevel::Level(index) = level else {
+            return true;
+        };
+        let Some(previous) = index.checked_sub(1) else {
+            return true;
+        };
+        let previous_best = self.best_score(SelectedLevel::Level(previous));
+        definition.unlock_score <= previous_best.unwrap_or_default()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn load() -> Self {
+        let Ok(content) = std::fs::read_to_string(RECORDS_FILE) else {
+            return Self::default();
+        };
+        ron::from_str(&content)
+            .inspect_err(|err| warn!("Could not read {RECORDS_FILE}: {err}"))
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn load() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                std::fs::write(RECORDS_FILE, content).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Could not save {RECORDS_FILE}: {err}");
+        }
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn save(&self) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Resource, Asset, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct LevelDefinition {
+    pub name: String,
+    /// Length of the road along the x-axis.
+    pub road_length: f32,
+    /// The road tiles across the road, from -z to +z. Each tile is [`LANEWIDTH`] wide.
+    #[serde(default)]
+    pub tiles: Vec<RoadTile>,
+    /// Straight lanes along the road.
+    #[serde(default)]
+    pub lanes: Vec<LaneDefinition>,
+    /// Curved lanes, crossings and junctions.
+    #[serde(default)]
+    pub network: RoadNetworkDefinition,
+    #[serde(default)]
+    pub scenery: SceneryDefinition,
+    /// Pertubators that can be placed in this level. All of them, if empty.
+    #[serde(default)]
+    pub allowed_pertubators: Vec<Pertubator>,
+    /// Areas in which no pertubators can be placed.
+    #[serde(default)]
+    pub restricted_zones: Vec<RestrictedZone>,
+    /// Areas with another surface than asphalt.
+    #[serde(default)]
+    pub surfaces: Vec<SurfaceZone>,
+    /// Shown in the level selection.
+    #[serde(default)]
+    pub description: String,
+    /// Score needed on the previous level to unlock this one.
+    #[serde(default)]
+    pub unlock_score: f32,
+    /// Path of the music played during the level.
+    #[serde(default = "default_music")]
+    pub music: String,
+    /// Stems played along with the music, which fade in as the chaos on the road rises.
+    #[serde(default)]
+    pub music_layers: Vec<MusicLayer>,
+    /// Path of a short piece of music played on huge chain reactions.
+    #[serde(default)]
+    pub music_stinger: Option<String>,
+}
+
+pub fn default_music() -> String {
+    "audio/music/FreakyWaves - CrashThemAll.ogg".into()
+}
+
+/// A stem of the level music.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct MusicLayer {
+    /// Path of the audio file. It should be as long as the music, to stay in sync when looping.
+    pub path: String,
+    /// Chaos from 0 to 1 at which the layer is fully audible.
+    pub threshold: f32,
+}
+
+/// Visual tile of the road. Separators are solid barriers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum RoadTile {
+    Straight,
+    Border,
+    Separator,
+}
+
+/// A lane cars drive on, with the settings of its car spawner.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct LaneDefinition {
+    /// Distance of the lane's center from the -z edge of the road, in tiles.
+    pub position: f32,
+    pub direction: LaneDirection,
+    pub target_velocity: f32,
+    /// Bounds of the random time between two spawned cars in seconds.
+    pub min_spawn_interval: f32,
+    pub max_spawn_interval: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum LaneDirection {
+    /// Towards +x.
+    LeftToRight,
+    /// Towards -x.
+    RightToLeft,
+}
+
+impl LaneDirection {
+    pub fn vector(self) -> Vec3 {
+        match self {
+            LaneDirection::LeftToRight => Vec3::X,
+            LaneDirection::RightToLeft => Vec3::NEG_X,
+        }
+    }
+}
+
+/// An area on the ground (x, z) in which no pertubators can be placed.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct RestrictedZone {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl RestrictedZone {
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+/// The surface of the ground, changing the grip and the rolling resistance of the wheels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum Surface {
+    #[default]
+    Asphalt,
+    Gravel,
+    Ice,
+    /// Oil-stained asphalt.
+    Oil,
+    Wet,
+}
+
+impl Surface {
+    pub const ALL: [Surface; 5] = [
+        Surface::Asphalt,
+        Surface::Gravel,
+        Surface::Ice,
+        Surface::Oil,
+        Surface::Wet,
+    ];
+}
+
+/// An area on the ground (x, z) with its own surface.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct SurfaceZone {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub surface: Surface,
+}
+
+impl SurfaceZone {
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+/// Decoration around the road.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneryDefinition {
+    /// Rows of grass tufts on each side of the road.
+    pub grass_rows: u32,
+    /// Props placed by hand.
+    pub props: Vec<PropPlacement>,
+    /// Scales the number of trees and buildings placed around the road.
+    pub density: f32,
+    /// Areas with a different density, multiplied with `density`. The first matching area counts.
+    pub density_map: Vec<DensityArea>,
+    /// Whether fences line the road.
+    pub fences: bool,
+}
+
+impl Default for SceneryDefinition {
+    fn default() -> Self {
+        Self {
+            grass_rows: 25,
+            props: vec![],
+            density: 1.,
+            density_map: vec![],
+            fences: true,
+        }
+    }
+}
+
+impl SceneryDefinition {
+    /// The density of props at the point on the ground (x, z), from 0 (none) to 1 (full).
+    pub fn density_at(&self, point: Vec2) -> f32 {
+        let local = self
+            .density_map
+            .iter()
+            .find(|area| point.cmpge(area.min).all() && point.cmple(area.max).all())
+            .map_or(1., |area| area.density);
+        (self.density * local).clamp(0., 1.)
+    }
+}
+
+/// An area on the ground (x, z) with its own scenery density.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct DensityArea {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub density: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct PropPlacement {
+    pub kind: PropKind,
+    /// Position on the ground (x, z).
+    pub position: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum PropKind {
+    Grass,
+    GrassLarge,
+    Tree,
+    /// A piece of fence, destructible.
+    Fence,
+    /// A road sign, destructible.
+    Sign,
+    Building,
+}
+
+impl LevelDefinition {
+    /// Total width of the road along the z-axis.
+    pub fn road_width(&self) -> f32 {
+        self.tiles.len() as f32 * LANEWIDTH
+    }
+
+    /// The z-coordinate of the center of the lane.
+    pub fn lane_z(&self, lane: &LaneDefinition) -> f32 {
+        -self.road_width() / 2. + lane.position * LANEWIDTH
+    }
+
+    pub fn allows(&self, pertubator: Pertubator) -> bool {
+        self.allowed_pertubators.is_empty() || self.allowed_pertubators.contains(&pertubator)
+    }
+
+    /// Whether the position lies in a restricted zone.
+    pub fn is_restricted(&self, position: Vec3) -> bool {
+        self.restricted_zones
+            .iter()
+            .any(|zone| zone.contains(position.xz()))
+    }
+
+    /// The surface at the position. The first zone containing it counts, asphalt is the default.
+    pub fn surface_at(&self, position: Vec3) -> Surface {
+        self.surfaces
+            .iter()
+            .find(|zone| zone.contains(position.xz()))
+            .map_or(Surface::Asphalt, |zone| zone.surface)
+    }
+}
+
+fn load_selected_level(
+    mut commands: Commands,
+    selected: Res<SelectedLevel>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<LevelDefinition>>,
+    asset_server: Res<AssetServer>,
+) {
+    let SelectedLevel::Level(index) = *selected else {
+        // The highway is generated when the round starts.
+        commands.insert_resource(LevelMusic {
+            track: asset_server.load(default_music()),
+            layers: vec![],
+            stinger: None,
+        });
+        return;
+    };
+    let Some(level) = levels.get(&level_assets.levels[index]) else {
+        error!("Level {} is not loaded", selected.path());
+        return;
+    };
+
+    commands.insert_resource(LevelMusic::load(&asset_server, level));
+    commands.insert_resource(level.clone());
+}
+
+/// Generates the highway of the round from its seed. The difficulty rises with the score.
+pub fn generate_endless_highway(
+    mut commands: Commands,
+    seed: Res<RoundSeed>,
+    high_score: Res<HighScore>,
+) {
+    let parameters = HighwayParameters {
+        difficulty: (high_score.get() / ENDLESS_MAX_DIFFICULTY_SCORE).clamp(0., 1.),
+        ..default()
+    };
+    commands.insert_resource(generate_highway(seed.0, &parameters));
+}
+
+/// Updates the current level whenever its file is modified.
+fn reload_selected_level(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<LevelDefinition>>,
+    selected: Res<SelectedLevel>,
+    level_assets: Option<Res<LevelAssets>>,
+    levels: Res<Assets<LevelDefinition>>,
+) {
+    let (Some(level_assets), SelectedLevel::Level(index)) = (level_assets, *selected) else {
+        return;
+    };
+    let handle = &level_assets.levels[index];
+
+    for event in events.read() {
+        if event.is_modified(handle) {
+            if let Some(level) = levels.get(handle) {
+                info!("Reloaded {}", selected.path());
+                commands.insert_resource(level.clone());
+            }
+        }
+    }
+}
+
+fn record_best_score(
+    mut records: ResMut<LevelRecords>,
+    selected: Res<SelectedLevel>,
+    high_score: Res<HighScore>,
+    playback: Option<Res<ReplayPlayback>>,
+    play_test: Option<Res<PlayTest>>,
+) {
+    // Watching a replay or play-testing a level does not set a record.
+    if playback.is_some() || play_test.is_some() {
+        return;
+    }
+
+    if records
+        .best_score(*selected)
+        .is_none_or(|best| high_score.get() > best)
+    {
+        records
+            .best_scores
+            .insert(selected.path().into(), high_score.get());
+        records.save();
+    }
+}
@@ -1,12 +1,28 @@
 use bevy::app::App;
 
+pub mod bullet_time;
 pub mod car;
 mod car_colliders;
 mod car_de_spawning;
-mod consts;
+pub mod consts;
+pub mod crash;
+mod engine_sound;
+mod highway_generator;
+pub mod layers;
+pub mod level;
+pub mod music;
 pub mod pertubator;
-mod points_money;
-mod road;
+pub mod points_money;
+pub mod replay;
+pub mod road;
+pub mod road_network;
+pub mod round;
+pub mod scenery;
+pub mod slow_motion;
+pub mod stats;
+mod surface;
+pub mod time_scale;
+pub mod tuning;
 pub mod ui;
 mod util;
 mod world;
@@ -14,14 +30,30 @@ mod world;
 pub(crate) fn plugin(app: &mut App) {
     app.add_plugins((
         road::plugin,
+        road_network::plugin,
         car::plugin,
         car_colliders::plugin,
+        crash::plugin,
         car_de_spawning::plugin,
         world::plugin,
         pertubator::plugin,
         util::plugin,
         points_money::plugin,
         ui::plugin,
+        round::plugin,
+        replay::plugin,
+    ));
+    app.add_plugins((
+        stats::plugin,
+        tuning::plugin,
+        level::plugin,
+        scenery::plugin,
+        surface::plugin,
+        time_scale::plugin,
+        slow_motion::plugin,
+        bullet_time::plugin,
+        music::plugin,
+        engine_sound::plugin,
     ));
 }
 
@@ -0,0 +1,163 @@
+//! The music of a round, following the chaos on the road.
+//!
+//! The chaos rises with big crashes, wrecks and explosions, and calms down over time. The music
+//! gets louder with it, and the intensity layers of the level fade in one after the other. Huge
+//! chain reactions play a stinger.
+//!
+//! No level ships stems or a stinger yet, so for now only the volume of the music follows the
+//! chaos. The highway level shows how to add them.
+//!
+//! The soundtrack starts and stops the music, see [`level_music`].
+
+use bevy::{audio::Volume, ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    audio::{Music, VolumeFade, music},
+    screens::Screen,
+};
+
+use super::{
+    car::Wrecked, crash::CarCrash, level::LevelMusic, pertubator::Explosion,
+    slow_motion::ChainReaction, tuning::GameTuning,
+};
+
+/// Chaos per second the music follows the measured chaos with.
+const CHAOS_FOLLOW_SPEED: f32 = 0.5;
+/// Range of chaos below the threshold of a layer over which it fades in.
+const LAYER_FADE_RANGE: f32 = 0.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Chaos>();
+    app.register_type::<GameMusic>();
+    app.register_type::<MusicLayer>();
+    app.init_resource::<Chaos>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), reset_chaos);
+    app.add_systems(
+        FixedUpdate,
+        heat_up_on_crashes
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        (
+            (measure_chaos, fade_music).chain(),
+            play_stinger.run_if(on_event::<ChainReaction>),
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// How much is going on during the round.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Chaos {
+    /// From 0 (calm) to 1 (the highway falls apart), smoothed for the music.
+    pub level: f32,
+    /// Recent crash severities, decaying over time.
+    crash_heat: f32,
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct GameMusic;
+
+/// An intensity layer of the game music.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct MusicLayer {
+    /// Chaos at which the layer is fully audible.
+    threshold: f32,
+}
+
+fn reset_chaos(mut chaos: ResMut<Chaos>) {
+    *chaos = Chaos::default();
+}
+
+/// The music of the level with all its layers, so they play in sync.
+pub fn level_music(level_music: &LevelMusic) -> impl Bundle {
+    let layers = level_music.layers.clone().into_iter();
+    (
+        music(level_music.track.clone()),
+        GameMusic,
+        Children::spawn(SpawnIter(layers.map(|(layer, threshold)| {
+            (
+                Name::new("Game Music Layer"),
+                AudioPlayer(layer),
+                PlaybackSettings::LOOP.with_volume(Volume::SILENT),
+                Music,
+                MusicLayer { threshold },
+                VolumeFade(0.),
+            )
+        }))),
+    )
+}
+
+fn heat_up_on_crashes(
+    mut car_crashes: EventReader<CarCrash>,
+    mut chaos: ResMut<Chaos>,
+    tuning: Res<GameTuning>,
+) {
+    for car_crash in car_crashes.read() {
+        chaos.crash_heat += car_crash.severity * tuning.chaos_per_severity;
+    }
+}
+
+fn measure_chaos(
+    mut chaos: ResMut<Chaos>,
+    wrecks: Query<(), With<Wrecked>>,
+    explosions: Query<(), With<Explosion>>,
+    tuning: Res<GameTuning>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    chaos.crash_heat *= (-delta / tuning.chaos_decay_seconds).exp();
+
+    let measured = (chaos.crash_heat
+        + wrecks.iter().count() as f32 * tuning.chaos_per_wreck
+        + explosions.iter().count() as f32 * tuning.chaos_per_explosion)
+        .min(1.);
+    let step = CHAOS_FOLLOW_SPEED * delta;
+    chaos.level += (measured - chaos.level).clamp(-step, step);
+}
+
+/// Sets the volume of the music and its layers according to the chaos.
+fn fade_music(
+    chaos: Res<Chaos>,
+    mut tracks: Query<&mut PlaybackSettings, With<GameMusic>>,
+    mut layers: Query<(&mut PlaybackSettings, &MusicLayer), Without<GameMusic>>,
+    tuning: Res<GameTuning>,
+) {
+    for mut playback in &mut tracks {
+        let linear = tuning.music_calm_volume.lerp(1., chaos.level);
+        playback.volume = Volume::Linear(linear);
+    }
+    for (mut playback, layer) in &mut layers {
+        let fade_start = layer.threshold - LAYER_FADE_RANGE;
+        let linear = ((chaos.level - fade_start) / LAYER_FADE_RANGE).clamp(0., 1.);
+        playback.volume = Volume::Linear(linear);
+    }
+}
+
+fn play_stinger(
+    mut commands: Commands,
+    mut chain_reactions: EventReader<ChainReaction>,
+    level_music: Res<LevelMusic>,
+) {
+    chain_reactions.clear();
+    let Some(stinger) = &level_music.stinger else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Music Stinger"),
+        StateScoped(Screen::Gameplay),
+        AudioPlayer(stinger.clone()),
+        PlaybackSettings::DESPAWN,
+        Music,
+    ));
+}
@@ -8,24 +8,31 @@ use bevy::{
     scene::SceneInstanceReady,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
     AppSystems, PausableSystems,
     asset_tracking::LoadResource,
+    audio::SoundEffect,
     game::{
         car::{CarAssets, Wrecked},
         car_colliders::WheelCollider,
+        level::LevelDefinition,
+        replay::ReplayPlayback,
         road::RoadsOrigin,
+        road_network::RoadPathMesh,
+        tuning::GameTuning,
     },
     screens::Screen,
 };
 
 use super::{
-    car::{Car, CarCrashable},
+    car::Car,
+    crash::{CarCrashable, PreStepVelocity},
+    layers::GameLayer,
     util::Lifetime,
 };
 
-const BARREL_SPHERE_SIZE: f32 = 15.0;
-const BARREL_EXPLOSION_STRENGTH: f32 = 50.0;
 const EXPLOSION_EXPANSION_FACTOR: f32 = 40.0;
 
 pub(super) fn plugin(app: &mut App) {
@@ -42,6 +49,9 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<UnlockedPertubators>();
     app.register_type::<Money>();
 
+    app.add_event::<PlacePertubator>();
+    app.add_event::<PertubatorTriggered>();
+
     app.add_systems(OnEnter(Screen::Gameplay), spawn_preview);
     app.add_systems(
         Update,
@@ -53,7 +63,10 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         FixedUpdate,
-        update_springs
+        (
+            place_pertubators.run_if(in_state(Screen::Gameplay)),
+            update_springs,
+        )
             .in_set(AppSystems::Update)
             .in_set(PausableSystems),
     );
@@ -130,7 +143,7 @@ impl PertubatorAsset {
 
 /// This defines every Pertubator we have
 /// For every addition extend the name and spawn implementations
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Component, Reflect)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component)]
 pub enum Pertubator {
     Spring,
@@ -158,6 +171,16 @@ impl Pertubator {
         }
     }
 
+    /// Layers of the colliders setting off this pertubator.
+    pub fn trigger_layers(&self) -> LayerMask {
+        match self {
+            Pertubator::Spring => [GameLayer::Car, GameLayer::Wheel].into(),
+            Pertubator::Nails => GameLayer::Wheel.into(),
+            Pertubator::Soap => GameLayer::Wheel.into(),
+            Pertubator::Barrel => GameLayer::Car.into(),
+        }
+    }
+
     pub fn scale(&self) -> Vec3 {
         match self {
             Pertubator::Spring => Vec3::ONE,
@@ -193,7 +216,10 @@ impl Pertubator {
                         *self,
                         Transform::from_translation(position.with_y(spring_y_position(0.))),
                         RigidBody::Kinematic,
+                        TransformInterpolation,
+                        PreStepVelocity::default(),
                         Collider::cylinder(1.0, 4.0),
+                        GameLayer::Pertubator.collision_layers(),
                         Visibility::Visible,
                         CarCrashable,
                     ))
@@ -204,12 +230,14 @@ impl Pertubator {
                                 RigidBody::Static,
                                 Sensor,
                                 Collider::sphere(0.4),
+                                GameLayer::pertubator_sensor(self.trigger_layers()),
                                 CollisionEventsEnabled,
                                 Transform::from_xyz(0., 2., 0.),
                             ))
                             .observe(
                                 |trigger: Trigger<OnCollisionStart>,
                                  mut commands: Commands,
+                                 mut triggered: EventWriter<PertubatorTriggered>,
                                  possible_spring_sensors: Query<&ChildOf, With<Sensor>>,
                                  car_parts: Query<
                                     (Option<&Car>, Option<&ChildOf>),
@@ -228,6 +256,10 @@ impl Pertubator {
 
                                         commands.entity(car_entity).insert(Wrecked);
                                         commands.entity(spring).insert(Lifetime::new(2.));
+                                        triggered.write(PertubatorTriggered {
+                                            pertubator: Pertubator::Spring,
+                                            wrecks: 1,
+                                        });
                                         commands
                                             .entity(spring_sensor)
                                             .remove::<CollisionEventsEnabled>();
@@ -252,17 +284,24 @@ impl Pertubator {
                         RigidBody::Static,
                         Collider::cylinder(0.5, 0.5),
                         Sensor,
+                        GameLayer::pertubator_sensor(self.trigger_layers()),
                         CollisionEventsEnabled,
                     ))
                     .observe(
                         |trigger: Trigger<OnCollisionStart>,
                          mut commands: Commands,
-                         wheels: Query<Entity, With<WheelCollider>>| {
+                         mut triggered: EventWriter<PertubatorTriggered>,
+                         wheels: Query<&ChildOf, With<WheelCollider>>,
+                         intact_cars: Query<(), (With<Car>, Without<Wrecked>)>| {
                             let nails = trigger.target();
                             let other_entity = trigger.collider;
-                            if wheels.contains(other_entity) {
+                            if let Ok(car) = wheels.get(other_entity) {
                                 commands.entity(other_entity).insert(Nailed);
                                 commands.entity(nails).insert(Lifetime::new(1.0));
+                                triggered.write(PertubatorTriggered {
+                                    pertubator: Pertubator::Nails,
+                                    wrecks: intact_cars.contains(car.0) as u32,
+                                });
                                 // dbg!("Car {} triggered nails {}", other_entity, nails);
                             }
                         },
@@ -278,17 +317,24 @@ impl Pertubator {
                         RigidBody::Static,
                         Collider::cylinder(0.5, 0.5),
                         Sensor,
+                        GameLayer::pertubator_sensor(self.trigger_layers()),
                         CollisionEventsEnabled,
                     ))
                     .observe(
                         |trigger: Trigger<OnCollisionStart>,
                          mut commands: Commands,
-                         wheels: Query<Entity, With<WheelCollider>>| {
+                         mut triggered: EventWriter<PertubatorTriggered>,
+                         wheels: Query<&ChildOf, With<WheelCollider>>,
+                         intact_cars: Query<(), (With<Car>, Without<Wrecked>)>| {
                             let soap = trigger.target();
                             let other_entity = trigger.collider;
-                            if wheels.contains(other_entity) {
+                            if let Ok(car) = wheels.get(other_entity) {
                                 commands.entity(other_entity).insert(Soaped);
                                 commands.entity(soap).insert(Lifetime::new(1.0));
+                                triggered.write(PertubatorTriggered {
+                                    pertubator: Pertubator::Soap,
+                                    wrecks: intact_cars.contains(car.0) as u32,
+                                });
                                 // dbg!("Car {} triggered soap {}", other_entity, soap);
                             }
                         },
@@ -304,15 +350,18 @@ impl Pertubator {
                         RigidBody::Static,
                         Collider::cylinder(0.25, 0.25),
                         Sensor,
+                        GameLayer::pertubator_sensor(self.trigger_layers()),
                         CollisionEventsEnabled,
                     ))
                     .observe(
                         |trigger: Trigger<OnCollisionStart>,
                          mut commands: Commands,
+                         mut triggered: EventWriter<PertubatorTriggered>,
                          mut cars: Query<(&mut ExternalImpulse, &Transform), With<Car>>,
                          transform: Query<&Transform>,
                          spatial_query: SpatialQuery,
-                         car_assets: Res<CarAssets>| {
+                         car_assets: Res<CarAssets>,
+                         tuning: Res<GameTuning>| {
                             let barrel = trigger.target();
                             let other_entity = trigger.collider;
                             if cars.contains(other_entity) {
@@ -321,27 +370,33 @@ impl Pertubator {
 
                                 /* Get all info for the explosion*/
                                 let barrel_pos = transform.get(barrel).unwrap();
-                                let shape = Collider::sphere(BARREL_SPHERE_SIZE);
+                                let shape = Collider::sphere(tuning.barrel_explosion_radius);
                                 let intersections = spatial_query.shape_intersections(
                                     &shape,
                                     barrel_pos.translation,
                                     Quat::default(),
-                                    &SpatialQueryFilter::default(),
+                                    &SpatialQueryFilter::from_mask(GameLayer::Car),
                                 );
 
+                                let mut wrecks = 0;
                                 for entity in intersections.iter() {
                                     if let Ok((mut impulse, transform)) = cars.get_mut(*entity) {
+                                        wrecks += 1;
                                         commands.entity(*entity).insert(Wrecked);
                                         impulse.apply_impulse(
-                                            BARREL_EXPLOSION_STRENGTH
+                                            tuning.barrel_explosion_strength
                                                 * (transform.translation - barrel_pos.translation)
                                                     .normalize(),
                                         );
                                     }
                                 }
 
+                                triggered.write(PertubatorTriggered {
+                                    pertubator: Pertubator::Barrel,
+                                    wrecks,
+                                });
+
                                 /* Explosion sound */
-                                // TODO: Could not figure out good spatial sound
                                 commands.spawn((
                                     Name::new("Explosion Sound"),
                                     StateScoped(Screen::Gameplay),
@@ -349,7 +404,9 @@ impl Pertubator {
                                     Lifetime::new(0.5),
                                     AudioPlayer::new(car_assets.explosion_audio.clone()),
                                     PlaybackSettings::ONCE
-                                        .with_volume(bevy::audio::Volume::Decibels(-13.)),
+                                        .with_volume(bevy::audio::Volume::Decibels(-13.))
+                                        .with_spatial(true),
+                                    SoundEffect,
                                     Explosion,
                                     SceneRoot(car_assets.smoke.clone()),
                                 ));
@@ -401,28 +458,65 @@ fn update_springs(
 #[reflect(Resource)]
 pub struct ActivePertubator(pub Option<Pertubator>);
 
+/// Request to place a pertubator in the world.
+///
+/// Written for the player's input and by a replay playback. The pertubator is spawned in the next
+/// fixed update, so placements are tied to a simulation tick.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct PlacePertubator {
+    pub pertubator: Pertubator,
+    pub position: Vec3,
+}
+
+/// Sent whenever a pertubator affects a car.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct PertubatorTriggered {
+    pub pertubator: Pertubator,
+    /// Number of cars wrecked by it.
+    pub wrecks: u32,
+}
+
 /// Insert this on a picking enabled entity
 /// e.g. road
 pub fn spawn_pertubator(
     trigger: Trigger<Pointer<Pressed>>,
-    mut commands: Commands,
-    spatial_query: SpatialQuery,
+    mut placements: EventWriter<PlacePertubator>,
     active_pertubator: Res<ActivePertubator>,
-    pertubator_assets: Res<PertubatorAssets>,
+    level: Res<LevelDefinition>,
+    playback: Option<Res<ReplayPlayback>>,
 ) {
-    if let Some(pertubator) = active_pertubator.0 {
-        if let Some(position) = trigger.hit.position {
-            let mut entity_commands = commands.spawn(StateScoped(Screen::Gameplay));
-            pertubator.spawn(
-                &mut entity_commands,
+    /* The player's input is replaced by the recording during a playback */
+    if playback.is_some() {
+        return;
+    }
+
+    if let Some(pertubator) = active_pertubator.0.filter(|p| level.allows(*p)) {
+        if let Some(position) = trigger.hit.position.filter(|p| !level.is_restricted(*p)) {
+            placements.write(PlacePertubator {
+                pertubator,
                 position,
-                &spatial_query,
-                &pertubator_assets,
-            );
+            });
         }
     }
 }
 
+fn place_pertubators(
+    mut commands: Commands,
+    mut placements: EventReader<PlacePertubator>,
+    spatial_query: SpatialQuery,
+    pertubator_assets: Res<PertubatorAssets>,
+) {
+    for placement in placements.read() {
+        let mut entity_commands = commands.spawn(StateScoped(Screen::Gameplay));
+        placement.pertubator.spawn(
+            &mut entity_commands,
+            placement.position,
+            &spatial_query,
+            &pertubator_assets,
+        );
+    }
+}
+
 #[derive(Debug, Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct PertubatorPreview;
@@ -445,7 +539,9 @@ fn preview_pertubator(
     active_pertubator: Res<ActivePertubator>,
     pertubator_assets: Option<Res<PertubatorAssets>>,
     preview: Single<(&mut Visibility, &mut Transform, &mut SceneRoot), With<PertubatorPreview>>,
-    road_origins: Query<Entity, With<RoadsOrigin>>,
+    road_origins: Query<Entity, Or<(With<RoadsOrigin>, With<RoadPathMesh>)>>,
+    level: Option<Res<LevelDefinition>>,
+    playback: Option<Res<ReplayPlayback>>,
 ) {
     let _ = road_origins;
     /* Wait on asset load; There is probably a better way */
@@ -457,7 +553,13 @@ fn preview_pertubator(
     /* Hide the preview in case we do not have a hit or no active pertubator selected*/
     *visiblity = Visibility::Hidden;
 
-    let scale = if let Some(pertubator) = active_pertubator.0 {
+    /* Nothing can be placed while watching a replay */
+    if playback.is_some() {
+        return;
+    }
+
+    let allowed = |pertubator: &Pertubator| level.as_ref().is_some_and(|l| l.allows(*pertubator));
+    let scale = if let Some(pertubator) = active_pertubator.0.filter(allowed) {
         /* Update visuals based on pertubator */
         if active_pertubator.is_changed() {
             scene.0 = pertubator_assets.0.get(&pertubator).unwrap().scene.clone();
@@ -474,7 +576,8 @@ fn preview_pertubator(
         .filter_map(|interaction| interaction.get_nearest_hit())
     {
         if road_origins.contains(*entity) {
-            if let Some(point) = hit.position {
+            let restricted = |point: &Vec3| level.as_ref().is_some_and(|l| l.is_restricted(*point));
+            if let Some(point) = hit.position.filter(|point| !restricted(point)) {
                 transform.translation = point;
                 *transform = transform.with_scale(scale);
                 *visiblity = Visibility::Inherited;
@@ -533,7 +636,7 @@ pub struct Money(pub i32);
 
 #[derive(Debug, Default, Component, Reflect)]
 #[reflect(Component)]
-struct Explosion;
+pub struct Explosion;
 
 fn update_explosion_size_and_transparency(
     mut commands: Commands,
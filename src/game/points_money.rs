@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 
-use crate::game::car::CarCrash;
+use crate::game::crash::CarCrash;
 
-use super::pertubator::Money;
+use super::{pertubator::Money, tuning::GameTuning};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<HighScore>();
@@ -11,8 +11,6 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(Update, update_highscore_money);
 }
 
-const CAR_COLLISION_MULTIPLIER: f32 = 100.;
-
 /// HighScore in f32
 #[derive(Debug, Default, Resource, Reflect)]
 #[reflect(Resource)]
@@ -28,9 +26,10 @@ fn update_highscore_money(
     mut car_crashes: EventReader<CarCrash>,
     mut high_score: ResMut<HighScore>,
     mut money: ResMut<Money>,
+    tuning: Res<GameTuning>,
 ) {
     for car_crash in car_crashes.read() {
-        high_score.0 += car_crash.magnitude * CAR_COLLISION_MULTIPLIER;
-        money.0 += car_crash.magnitude as i32;
+        high_score.0 += car_crash.severity * tuning.crash_score_multiplier;
+        money.0 += car_crash.severity as i32;
     }
 }
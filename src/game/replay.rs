@@ -0,0 +1,442 @@
+//! Recording and playback of rounds.
+//!
+//! A round is fully determined by its [`RoundConfig`] and the pertubators placed during it, so a
+//! replay only stores those. Playing it back re-runs the simulation with the player's input
+//! replaced by the recorded placements.
+
+use std::time::Duration;
+
+use avian3d::prelude::{Physics, PhysicsTime};
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppSystems, PausableSystems, screens::Screen, theme::widget::UiAssets};
+
+use super::{
+    level::SelectedLevel,
+    pertubator::{Money, Pertubator, PlacePertubator, UnlockedPertubators},
+    points_money::HighScore,
+    road::SeparatorDamage,
+    round::{RoundConfig, RoundSeed, RoundTick, start_round},
+    time_scale::TimeScale,
+};
+
+/// Seconds skipped by a single scrub step.
+const SCRUB_STEP_SECS: f64 = 5.0;
+/// Playback speed used while seeking to a scrub target.
+const SEEK_SPEED: f32 = 16.0;
+const MIN_PLAYBACK_SPEED: f32 = 0.25;
+const MAX_PLAYBACK_SPEED: f32 = 8.0;
+
+#[cfg(not(target_family = "wasm"))]
+const REPLAY_DIRECTORY: &str = "replays";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ReplayRecorder>();
+    app.init_resource::<LastReplay>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (start_recording.after(start_round), spawn_replay_hud),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), (finish_recording, leave_playback));
+
+    app.add_systems(
+        FixedUpdate,
+        (
+            feed_recorded_placements
+                .in_set(AppSystems::RecordInput)
+                .run_if(resource_exists::<ReplayPlayback>),
+            record_placements
+                .in_set(AppSystems::Update)
+                .run_if(not(resource_exists::<ReplayPlayback>)),
+        )
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            toggle_playback_pause.run_if(input_just_pressed(KeyCode::Space)),
+            change_playback_speed,
+            scrub_playback,
+            update_playback_time,
+            update_replay_hud,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<ReplayPlayback>)),
+    );
+}
+
+/// A recorded round.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: RoundConfig,
+    pub placements: Vec<PertubatorPlacement>,
+    /// Length of the round in fixed updates.
+    pub length: u64,
+}
+
+/// A single pertubator placed by the player.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PertubatorPlacement {
+    pub pertubator: Pertubator,
+    pub position: Vec3,
+    /// The [`RoundTick`] the pertubator was placed in.
+    pub tick: u64,
+}
+
+impl Replay {
+    /// Converts a duration in seconds into fixed updates of this replay.
+    fn ticks(&self, secs: f64) -> u64 {
+        (secs * self.config.fixed_hz).round() as u64
+    }
+
+    fn secs(&self, ticks: u64) -> f64 {
+        ticks as f64 / self.config.fixed_hz
+    }
+}
+
+/// The replay of the round currently being played.
+#[derive(Debug, Default, Resource)]
+struct ReplayRecorder(Option<Replay>);
+
+/// The replay of the most recently finished round.
+#[derive(Debug, Default, Resource)]
+pub struct LastReplay(pub Option<Replay>);
+
+/// Present while a replay is played back instead of taking the player's input.
+#[derive(Debug, Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    /// Index of the next placement to feed into the simulation.
+    next_placement: usize,
+    speed: f32,
+    paused: bool,
+    /// Tick the playback is fast-forwarding to.
+    seek_to: Option<u64>,
+    /// Set while the round is restarted for scrubbing backwards.
+    restarting: bool,
+    /// The player's state before the playback started, restored afterwards.
+    player_state: RoundConfig,
+}
+
+impl ReplayPlayback {
+    /// Whether the playback fast-forwards to a scrub target.
+    pub fn is_seeking(&self) -> bool {
+        self.seek_to.is_some()
+    }
+}
+
+/// Starts playing back `replay`.
+///
+/// The round is (re)started through the loading screen.
+pub fn start_playback(
+    commands: &mut Commands,
+    replay: Replay,
+    player_state: RoundConfig,
+    next_screen: &mut NextState<Screen>,
+) {
+    // The level is loaded on the loading screen, before the round applies the rest of the config.
+    commands.insert_resource(replay.config.selected_level());
+    commands.insert_resource(ReplayPlayback {
+        replay,
+        next_placement: 0,
+        speed: 1.0,
+        paused: false,
+        seek_to: None,
+        restarting: false,
+        player_state,
+    });
+    next_screen.set(Screen::Loading);
+}
+
+/// Writes `replay` into the replay directory and returns the path of the new file.
+#[cfg(not(target_family = "wasm"))]
+pub fn save_replay(replay: &Replay) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    let directory = std::path::Path::new(REPLAY_DIRECTORY);
+    std::fs::create_dir_all(directory)?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let path = directory.join(format!("replay-{timestamp}-{}.ron", replay.config.seed));
+
+    let content = ron::ser::to_string_pretty(replay, ron::ser::PrettyConfig::default())?;
+    std::fs::write(&path, content)?;
+
+    Ok(path)
+}
+
+/// Reads a replay file written by [`save_replay`].
+#[cfg(not(target_family = "wasm"))]
+pub fn load_replay(path: &std::path::Path) -> Result<Replay, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(ron::from_str(&content)?)
+}
+
+/// Loads the most recently saved replay file, if there is any.
+#[cfg(not(target_family = "wasm"))]
+pub fn load_latest_replay() -> Option<Replay> {
+    let latest = std::fs::read_dir(REPLAY_DIRECTORY)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "ron"))
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())?;
+
+    load_replay(&latest.path())
+        .inspect_err(|err| warn!("Could not load replay {:?}: {err}", latest.path()))
+        .ok()
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<Res<ReplayPlayback>>,
+    seed: Res<RoundSeed>,
+    fixed_time: Res<Time<Fixed>>,
+    money: Res<Money>,
+    high_score: Res<HighScore>,
+    unlocked: Res<UnlockedPertubators>,
+    level: Res<SelectedLevel>,
+    separator_damage: Res<SeparatorDamage>,
+) {
+    // Watching a replay does not produce a new one.
+    if playback.is_some() {
+        recorder.0 = None;
+        return;
+    }
+
+    recorder.0 = Some(Replay {
+        config: RoundConfig::capture(
+            &seed,
+            &fixed_time,
+            &money,
+            &high_score,
+            &unlocked,
+            &level,
+            &separator_damage,
+        ),
+        placements: Vec::new(),
+        length: 0,
+    });
+}
+
+fn record_placements(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut placements: EventReader<PlacePertubator>,
+    tick: Res<RoundTick>,
+) {
+    let Some(replay) = recorder.0.as_mut() else {
+        placements.clear();
+        return;
+    };
+
+    for placement in placements.read() {
+        replay.placements.push(PertubatorPlacement {
+            pertubator: placement.pertubator,
+            position: placement.position,
+            tick: tick.0,
+        });
+    }
+}
+
+fn finish_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut last_replay: ResMut<LastReplay>,
+    tick: Res<RoundTick>,
+) {
+    if let Some(mut replay) = recorder.0.take() {
+        replay.length = tick.0;
+        last_replay.0 = Some(replay);
+    }
+}
+
+fn feed_recorded_placements(
+    mut playback: ResMut<ReplayPlayback>,
+    mut placements: EventWriter<PlacePertubator>,
+    tick: Res<RoundTick>,
+) {
+    let playback = playback.as_mut();
+    while let Some(placement) = playback.replay.placements.get(playback.next_placement) {
+        if placement.tick > tick.0 {
+            break;
+        }
+
+        placements.write(PlacePertubator {
+            pertubator: placement.pertubator,
+            position: placement.position,
+        });
+        playback.next_placement += 1;
+    }
+}
+
+fn toggle_playback_pause(mut playback: ResMut<ReplayPlayback>) {
+    playback.paused = !playback.paused;
+}
+
+fn change_playback_speed(mut playback: ResMut<ReplayPlayback>, input: Res<ButtonInput<KeyCode>>) {
+    if input.just_pressed(KeyCode::ArrowUp) {
+        playback.speed = (playback.speed * 2.).min(MAX_PLAYBACK_SPEED);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        playback.speed = (playback.speed / 2.).max(MIN_PLAYBACK_SPEED);
+    }
+}
+
+/// Scrubs through the replay with the arrow keys.
+///
+/// The simulation cannot run backwards, so scrubbing back restarts the round and fast-forwards to
+/// the target tick.
+fn scrub_playback(
+    mut playback: ResMut<ReplayPlayback>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    input: Res<ButtonInput<KeyCode>>,
+    tick: Res<RoundTick>,
+) {
+    let step = playback.replay.ticks(SCRUB_STEP_SECS);
+    let current = playback.seek_to.unwrap_or(tick.0);
+
+    if input.just_pressed(KeyCode::ArrowRight) {
+        playback.seek_to = Some((current + step).min(playback.replay.length));
+    }
+
+    if input.just_pressed(KeyCode::ArrowLeft) {
+        let target = current.saturating_sub(step);
+        playback.seek_to = Some(target);
+        if target < tick.0 {
+            playback.restarting = true;
+            playback.next_placement = 0;
+            next_screen.set(Screen::Loading);
+        }
+    }
+}
+
+/// Drives the virtual clock according to the playback controls.
+fn update_playback_time(
+    mut playback: ResMut<ReplayPlayback>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time_scale: ResMut<TimeScale>,
+    mut physics_time: ResMut<Time<Physics>>,
+    tick: Res<RoundTick>,
+) {
+    if playback.seek_to.is_some_and(|target| tick.0 >= target) {
+        playback.seek_to = None;
+    }
+
+    let finished = tick.0 >= playback.replay.length;
+    if playback.paused || (finished && playback.seek_to.is_none()) {
+        virtual_time.pause();
+        physics_time.pause();
+        return;
+    }
+
+    virtual_time.unpause();
+    physics_time.unpause();
+
+    if playback.seek_to.is_some() {
+        time_scale.playback = SEEK_SPEED;
+        // Allow catching up on several fixed updates per frame while seeking.
+        virtual_time.set_max_delta(Duration::from_secs(1));
+    } else {
+        time_scale.playback = playback.speed;
+        virtual_time.set_max_delta(Duration::from_millis(250));
+    }
+}
+
+/// Ends the playback when leaving the round, unless the round is only restarted for scrubbing.
+fn leave_playback(
+    mut commands: Commands,
+    playback: Option<ResMut<ReplayPlayback>>,
+    mut seed: ResMut<RoundSeed>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time_scale: ResMut<TimeScale>,
+    mut money: ResMut<Money>,
+    mut high_score: ResMut<HighScore>,
+    mut unlocked: ResMut<UnlockedPertubators>,
+    mut level: ResMut<SelectedLevel>,
+    mut separator_damage: ResMut<SeparatorDamage>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+
+    if playback.restarting {
+        playback.restarting = false;
+        return;
+    }
+
+    playback.player_state.apply(
+        &mut seed,
+        &mut fixed_time,
+        &mut money,
+        &mut high_score,
+        &mut unlocked,
+        &mut level,
+        &mut separator_damage,
+    );
+    time_scale.playback = 1.0;
+    virtual_time.set_max_delta(Duration::from_millis(250));
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+struct ReplayHud;
+
+fn spawn_replay_hud(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    ui_assets: Option<Res<UiAssets>>,
+) {
+    /* Headless runs play back without any UI */
+    let (Some(_), Some(ui_assets)) = (playback, ui_assets) else {
+        return;
+    };
+
+    commands.spawn((
+        Name::new("Replay HUD"),
+        StateScoped(Screen::Gameplay),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(9.),
+            left: Val::Px(12.),
+            ..default()
+        },
+        children![(
+            ReplayHud,
+            Text::default(),
+            TextFont {
+                font: ui_assets.font.clone(),
+                font_size: 24.,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    ));
+}
+
+fn update_replay_hud(
+    playback: Res<ReplayPlayback>,
+    tick: Res<RoundTick>,
+    mut hud: Single<&mut Text, With<ReplayHud>>,
+) {
+    let state = if playback.seek_to.is_some() {
+        "SEEKING".to_string()
+    } else if playback.paused {
+        "PAUSED".to_string()
+    } else if tick.0 >= playback.replay.length {
+        "FINISHED".to_string()
+    } else {
+        format!("{}x", playback.speed)
+    };
+
+    hud.0 = format!(
+        "REPLAY {state}  {:.0}s / {:.0}s\n[Space] pause  [Up/Down] speed  [Left/Right] scrub",
+        playback.replay.secs(tick.0.min(playback.replay.length)),
+        playback.replay.secs(playback.replay.length),
+    );
+}
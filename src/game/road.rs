@@ -1,65 +1,130 @@
+use std::collections::BTreeMap;
+
 use avian3d::prelude::*;
-use bevy::{prelude::*, render::mesh::CuboidMeshBuilder};
+use bevy::{color::palettes::css::LIGHT_GRAY, prelude::*, render::mesh::CuboidMeshBuilder};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    AppSystems, PausableSystems,
     asset_tracking::LoadResource,
-    game::{car::CarCrashable, car_de_spawning::create_car_spawner, pertubator::spawn_pertubator},
+    game::{
+        crash::{CarCrash, CarCrashable, CrashKind},
+        pertubator::spawn_pertubator,
+    },
     screens::Screen,
 };
 
-use super::consts::{GROUNDFRICTION, LANEWIDTH, ROADLENGTH};
-
-#[derive(Debug, Reflect, PartialEq, Eq, Clone, Copy)]
+use super::{
+    consts::LANEWIDTH,
+    layers::GameLayer,
+    level::{LevelDefinition, RoadTile, generate_endless_highway},
+    round::start_round,
+    tuning::GameTuning,
+    util::Lifetime,
+};
 
-enum LaneType {
-    Border,
-    LeftToRight,
-    Separator,
-    RightToLeft,
-}
+/// Number of chunks a broken separator falls apart into.
+const SEPARATOR_CHUNKS: usize = 6;
+const SEPARATOR_CHUNK_SIZE: f32 = 0.5;
 
 #[derive(Debug, Default, Component, Reflect)]
 pub struct RoadsOrigin;
 
 #[derive(Debug, Default, Component, Reflect)]
-struct Road;
+pub struct Road;
+
+/// A separator tile which can still be broken.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct Separator {
+    row: usize,
+    index: u32,
+}
+
+/// Damage the separators of the current level took from crashes.
+///
+/// The damage stays between the rounds on the same level until it is repaired in the shop.
+#[derive(Debug, Default, Clone, PartialEq, Resource, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct SeparatorDamage {
+    /// Name of the level the damage belongs to.
+    pub level: String,
+    /// Damage of each hit separator, by tile row and index along the road.
+    pub damage: BTreeMap<(usize, u32), f32>,
+}
+
+impl SeparatorDamage {
+    pub fn is_broken(&self, row: usize, index: u32, tuning: &GameTuning) -> bool {
+        self.damage
+            .get(&(row, index))
+            .is_some_and(|damage| *damage >= tuning.separator_health)
+    }
+
+    /// Money needed to repair all separators.
+    pub fn repair_cost(&self, tuning: &GameTuning) -> i32 {
+        let damage: f32 = self
+            .damage
+            .values()
+            .map(|damage| damage.min(tuning.separator_health))
+            .sum();
+        (damage * tuning.separator_repair_cost).ceil() as i32
+    }
+}
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<RoadAssets>();
     app.load_resource::<RoadAssets>();
+    app.register_type::<Separator>();
+    app.register_type::<SeparatorDamage>();
+    app.init_resource::<SeparatorDamage>();
 
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_roads);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (
+            // The damage of a replayed round is applied when it starts.
+            spawn_roads.after(start_round),
+            spawn_restricted_zones,
+        )
+            .after(generate_endless_highway),
+    );
+    app.add_systems(
+        FixedUpdate,
+        damage_separators
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        apply_ground_friction_tuning.run_if(resource_exists_and_changed::<GameTuning>),
+    );
 }
 
 /// Spawn the visuals and the collider of the road.
 pub fn spawn_roads(
     mut commands: Commands,
     road_assets: Res<RoadAssets>,
+    level: Res<LevelDefinition>,
+    tuning: Res<GameTuning>,
+    mut separator_damage: ResMut<SeparatorDamage>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    let lanes = [
-        LaneType::Separator,
-        LaneType::Border,
-        LaneType::LeftToRight,
-        LaneType::LeftToRight,
-        LaneType::Border,
-        LaneType::Separator,
-        LaneType::Border,
-        LaneType::RightToLeft,
-        LaneType::RightToLeft,
-        LaneType::Border,
-        LaneType::Separator,
-    ];
-
-    let tiles_per_lane = (ROADLENGTH / LANEWIDTH).round() as u32;
-
-    let total_span_z = lanes.len() as f32 * LANEWIDTH;
+    // Damage from another level does not apply here.
+    if separator_damage.level != level.name {
+        *separator_damage = SeparatorDamage {
+            level: level.name.clone(),
+            ..default()
+        };
+    }
+
+    let tiles_per_lane = (level.road_length / LANEWIDTH).round() as u32;
+
+    let total_span_z = level.road_width();
     // Start in the neg-neg-quadrant direction. + half lanewidth due to centered meshes.
-    let start_x = -ROADLENGTH / 2. + LANEWIDTH / 2.;
+    let start_x = -level.road_length / 2. + LANEWIDTH / 2.;
     let start_z = -total_span_z / 2. + LANEWIDTH / 2.;
 
-    let mut car_spawner_info = vec![];
-
     commands
         .spawn((
             RoadsOrigin,
@@ -69,10 +134,12 @@ pub fn spawn_roads(
             Visibility::default(),
             RigidBody::Static,
             Collider::half_space(Vec3::Y),
-            Friction::new(GROUNDFRICTION),
+            GameLayer::Ground.collision_layers(),
+            Friction::new(tuning.ground_friction),
+            CarCrashable,
             Mesh3d(
                 meshes.add(CuboidMeshBuilder::default().build().scaled_by(Vec3::new(
-                    ROADLENGTH,
+                    level.road_length,
                     0.1,
                     total_span_z,
                 ))), /* I dont like this */
@@ -81,79 +148,178 @@ pub fn spawn_roads(
         ))
         .observe(spawn_pertubator)
         .with_children(|parent| {
-
-            for (lane_index, lane) in lanes.iter().enumerate() {
-                let lane_asset: &Handle<Scene> = match lane {
-
-                    LaneType::Border => &road_assets.road_border,
-                    LaneType::Separator => &road_assets.road_separator,
-                    _ => &road_assets.road_straight,
+            for (tile_row, tile) in level.tiles.iter().enumerate() {
+                let tile_asset: &Handle<Scene> = match tile {
+                    RoadTile::Border => &road_assets.road_border,
+                    RoadTile::Separator => &road_assets.road_separator,
+                    RoadTile::Straight => &road_assets.road_straight,
                 };
 
-
                 for tile_index in 0..tiles_per_lane {
+                    let broken = *tile == RoadTile::Separator
+                        && separator_damage.is_broken(tile_row, tile_index, &tuning);
+                    let tile_asset = if broken {
+                        &road_assets.road_straight
+                    } else {
+                        tile_asset
+                    };
+
                     let pos: Vec3 = Vec3::new(
                         start_x + tile_index as f32 * LANEWIDTH,
                         0.,
-                        start_z + lane_index as f32 * LANEWIDTH,
+                        start_z + tile_row as f32 * LANEWIDTH,
                     );
 
-
                     let mut segment = parent.spawn((
                         Road,
                         Name::new("Road"),
                         StateScoped(Screen::Gameplay),
                         Transform::from_translation(pos).with_scale(Vec3::splat(4.)),
-                        SceneRoot(lane_asset.clone()),
+                        SceneRoot(tile_asset.clone()),
                     ));
 
-                    if *lane == LaneType::Separator {
-                        segment.insert((RigidBody::Static, Collider::cuboid(1.0, 0.75, 0.8), CarCrashable));
+                    if *tile == RoadTile::Separator && !broken {
+                        segment.insert((
+                            RigidBody::Static,
+                            Collider::cuboid(1.0, 0.75, 0.8),
+                            GameLayer::Barrier.collision_layers(),
+                            CarCrashable,
+                            Separator {
+                                row: tile_row,
+                                index: tile_index,
+                            },
+                        ));
                     }
                 }
+            }
+        });
+}
 
-                if *lane == LaneType::LeftToRight
-                    || *lane == LaneType::RightToLeft
-                    || *lane == LaneType::Border
-                {
-                    /* Hackery because a segment does not equal to a lane */
-                    let mut current_lane = *lane;
-                    if *lane == LaneType::Border {
-                        if let Some(next_segment) = lanes.get(lane_index + 1) {
-                            if *next_segment == LaneType::LeftToRight || *next_segment == LaneType::RightToLeft {
-                                current_lane = *next_segment;
-                            }
-                        }
-
-                        /* If it did not change it is a "border segment", we dont care about spawning a spawner in */
-                        if current_lane == *lane {
-                            continue;
-                        }
-                    }
+/// Adds the severity of crashes to the damage of the separators, and breaks them apart when their
+/// health is used up. A broken separator leaves a gap cars can cross.
+fn damage_separators(
+    mut commands: Commands,
+    mut car_crashes: EventReader<CarCrash>,
+    separators: Query<(&Separator, &Transform, &ChildOf)>,
+    mut separator_damage: ResMut<SeparatorDamage>,
+    road_assets: Res<RoadAssets>,
+    tuning: Res<GameTuning>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut chunk_assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+) {
+    for car_crash in car_crashes.read() {
+        if car_crash.kind != CrashKind::Separator {
+            continue;
+        }
+        let Ok((separator, transform, child_of)) = separators.get(car_crash.other) else {
+            continue;
+        };
+        // Several cars may break the same separator in one step.
+        if separator_damage.is_broken(separator.row, separator.index, &tuning) {
+            continue;
+        }
 
-                    let direction = if current_lane == LaneType::LeftToRight {
-                        Vec3::X
-                    } else {
-                        Vec3::NEG_X
-                    };
+        *separator_damage
+            .damage
+            .entry((separator.row, separator.index))
+            .or_default() += car_crash.severity;
+        if !separator_damage.is_broken(separator.row, separator.index, &tuning) {
+            continue;
+        }
 
-                    car_spawner_info.push((
-                        direction,
-                        start_z + lane_index as f32 * LANEWIDTH + LANEWIDTH / 2.0,
-                    ));
-                }
-            }
-        });
+        // The gap gets the plain road of a straight tile.
+        commands.entity(car_crash.other).despawn();
+        commands.spawn((
+            Road,
+            Name::new("Road"),
+            StateScoped(Screen::Gameplay),
+            *transform,
+            SceneRoot(road_assets.road_straight.clone()),
+            ChildOf(child_of.parent()),
+        ));
 
-    for car_spawner_info in car_spawner_info.iter() {
-        commands.spawn(create_car_spawner(
-            car_spawner_info.1,
-            car_spawner_info.0,
-            5.,
+        let (mesh, material) = chunk_assets
+            .get_or_insert_with(|| {
+                (
+                    meshes.add(Cuboid::from_length(SEPARATOR_CHUNK_SIZE)),
+                    materials.add(Color::from(LIGHT_GRAY)),
+                )
+            })
+            .clone();
+        // The chunks don't touch the cars, so their trajectories are purely cosmetic.
+        let rng = &mut rand::thread_rng();
+        for _ in 0..SEPARATOR_CHUNKS {
+            let offset = Vec3::new(
+                rng.gen_range(-1.5..1.5),
+                rng.gen_range(0.5..1.5),
+                rng.gen_range(-0.3..0.3),
+            );
+            let direction = Vec3::new(offset.x, 1., rng.gen_range(-1.0..1.0)).normalize();
+            commands.spawn((
+                Name::new("Separator Chunk"),
+                StateScoped(Screen::Gameplay),
+                Transform::from_translation(transform.translation + offset),
+                Lifetime::new(4.0),
+                RigidBody::Dynamic,
+                TransformInterpolation,
+                Collider::cuboid(
+                    SEPARATOR_CHUNK_SIZE,
+                    SEPARATOR_CHUNK_SIZE,
+                    SEPARATOR_CHUNK_SIZE,
+                ),
+                GameLayer::Debris.collision_layers(),
+                Mass(10.0),
+                ExternalImpulse::new(tuning.debris_impulse * direction).with_persistence(false),
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+            ));
+        }
+    }
+}
+
+/// Marks the restricted zones of the level on the ground.
+fn spawn_restricted_zones(
+    mut commands: Commands,
+    level: Res<LevelDefinition>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if level.restricted_zones.is_empty() {
+        return;
+    }
+
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgba(1., 0., 0., 0.25),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    for zone in &level.restricted_zones {
+        let center = (zone.min + zone.max) / 2.;
+        commands.spawn((
+            Name::new("Restricted Zone"),
+            StateScoped(Screen::Gameplay),
+            // Above the road and the path meshes.
+            Transform::from_xyz(center.x, 0.06, center.y),
+            Mesh3d(meshes.add(Plane3d::new(Vec3::Y, (zone.max - zone.min) / 2.))),
+            MeshMaterial3d(material.clone()),
+            Pickable::IGNORE,
         ));
     }
 }
 
+/// Applies a changed ground friction of the [`GameTuning`] to the road.
+fn apply_ground_friction_tuning(
+    mut roads: Query<&mut Friction, With<RoadsOrigin>>,
+    tuning: Res<GameTuning>,
+) {
+    for mut friction in &mut roads {
+        friction.dynamic_coefficient = tuning.ground_friction;
+        friction.static_coefficient = tuning.ground_friction;
+    }
+}
+
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct RoadAssets {
@@ -178,3 +344,23 @@ impl FromWorld for RoadAssets {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repair_cost_caps_damage_at_separator_health() {
+        let tuning = GameTuning {
+            separator_health: 10.,
+            separator_repair_cost: 2.,
+            ..default()
+        };
+        let separator_damage = SeparatorDamage {
+            level: "test".into(),
+            damage: BTreeMap::from([((0, 0), 4.), ((0, 1), 10.), ((2, 5), 250.)]),
+        };
+        assert_eq!(separator_damage.repair_cost(&tuning), (4 + 10 + 10) * 2);
+        assert_eq!(SeparatorDamage::default().repair_cost(&tuning), 0);
+    }
+}
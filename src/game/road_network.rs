@@ -0,0 +1,484 @@
+//! The paths cars drive along.
+//!
+//! Every lane of a level is a path. Straight lanes come from the level's `lanes`, curves, crossings
+//! and junctions from its `network`: splines through control points, which can continue into
+//! other paths. A car reaching the end of a path continues on a random one of the following
+//! paths, or is despawned if there is none. Car spawners sit at the start of the entry paths.
+//!
+//! Ramps are network paths as well. An on-ramp merges into the path following it: cars yield to
+//! the traffic there, until they lose their patience and force their way in. An off-ramp is an
+//! exit, which cars leave a path through by chance.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{
+    car::{Car, Wrecked},
+    car_de_spawning::{CarSpawner, create_car_spawner},
+    consts::{DISTANCEUNTILCARSREACHTHEROAD, LANEWIDTH, MAXCARHEIGHT},
+    level::{LevelDefinition, generate_endless_highway},
+    pertubator::spawn_pertubator,
+    round::GameRng,
+    tuning::GameTuning,
+};
+
+/// Points sampled per spline segment between two control points.
+const SAMPLES_PER_SEGMENT: usize = 8;
+
+/// How far ahead on its path a car steers towards.
+const PATH_LOOKAHEAD: f32 = 2. * LANEWIDTH;
+
+/// Length of the end of a merging path, in which cars check the traffic they merge into.
+const MERGE_ZONE: f32 = 4. * LANEWIDTH;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PathFollower>();
+    app.register_type::<RoadPathMesh>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_road_network.after(generate_endless_highway),
+    );
+    app.add_systems(
+        FixedUpdate,
+        follow_paths
+            .in_set(AppSystems::RecordInput)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Curved paths, crossings and junctions of a level.
+#[derive(Debug, Default, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoadNetworkDefinition {
+    pub paths: Vec<PathDefinition>,
+    /// Paths cars are spawned on.
+    pub entries: Vec<EntryDefinition>,
+    /// Off-ramps and other places cars can leave a path.
+    pub exits: Vec<ExitDefinition>,
+}
+
+/// A lane of the level's `lanes` or a path of its `network`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum PathId {
+    Lane(usize),
+    Path(usize),
+}
+
+impl PathId {
+    /// Index of the path in [`RoadNetwork::paths`].
+    fn index(self, level: &LevelDefinition) -> usize {
+        match self {
+            PathId::Lane(index) => index,
+            PathId::Path(index) => level.lanes.len() + index,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct PathDefinition {
+    /// Control points on the ground (x, z), the path is a spline through them.
+    pub points: Vec<Vec2>,
+    /// The paths a car can continue on at the end of this one.
+    #[serde(default)]
+    pub next: Vec<PathId>,
+    /// Whether the path merges into traffic on the next paths, like an on-ramp.
+    #[serde(default)]
+    pub merge: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct EntryDefinition {
+    pub path: PathId,
+    pub target_velocity: f32,
+    /// Bounds of the random time between two spawned cars in seconds.
+    pub min_spawn_interval: f32,
+    pub max_spawn_interval: f32,
+}
+
+/// A place where cars can turn from one path onto another, like an off-ramp.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct ExitDefinition {
+    pub from: PathId,
+    /// Distance along `from` where cars turn off.
+    pub at: f32,
+    pub to: PathId,
+    /// Probability of a passing car to take the exit.
+    pub chance: f32,
+}
+
+/// All paths of the current level, sampled to polylines.
+#[derive(Debug, Default, Resource)]
+pub struct RoadNetwork {
+    pub paths: Vec<LanePath>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LanePath {
+    points: Vec<Vec3>,
+    /// Distance along the path up to each point.
+    distances: Vec<f32>,
+    next: Vec<usize>,
+    merge: bool,
+    exits: Vec<Exit>,
+}
+
+#[derive(Debug, Clone)]
+struct Exit {
+    at: f32,
+    to: usize,
+    chance: f32,
+}
+
+impl LanePath {
+    fn new(points: Vec<Vec3>, next: Vec<usize>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut distance = 0.;
+        for (index, point) in points.iter().enumerate() {
+            if index > 0 {
+                distance += point.distance(points[index - 1]);
+            }
+            distances.push(distance);
+        }
+
+        Self {
+            points,
+            distances,
+            next,
+            merge: false,
+            exits: vec![],
+        }
+    }
+
+    /// A path through the control points on the ground.
+    fn from_spline(control_points: &[Vec2], next: Vec<usize>) -> Option<Self> {
+        let control_points = control_points
+            .iter()
+            .map(|point| Vec3::new(point.x, 0., point.y));
+        let curve = CubicCardinalSpline::new_catmull_rom(control_points)
+            .to_curve()
+            .ok()?;
+        let samples = curve.segments().len() * SAMPLES_PER_SEGMENT;
+
+        Some(Self::new(curve.iter_positions(samples).collect(), next))
+    }
+
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or_default()
+    }
+
+    pub fn start(&self) -> Vec3 {
+        self.points.first().copied().unwrap_or_default()
+    }
+
+    /// The point at the distance along the path. Continues straight beyond its ends.
+    pub fn position_at(&self, distance: f32) -> Vec3 {
+        let index = self.segment_at(distance);
+        let (from, to) = (self.points[index], self.points[index + 1]);
+        let segment_length = self.distances[index + 1] - self.distances[index];
+        if segment_length <= 0. {
+            return from;
+        }
+
+        from.lerp(to, (distance - self.distances[index]) / segment_length)
+    }
+
+    /// The normalized driving direction at the distance along the path.
+    pub fn direction_at(&self, distance: f32) -> Vec3 {
+        let index = self.segment_at(distance);
+        (self.points[index + 1] - self.points[index]).normalize_or(Vec3::X)
+    }
+
+    /// Distance along the path of the point closest to `position`.
+    ///
+    /// Only the segments after the one at `from_distance` are searched, so cars never jump back
+    /// to an earlier part of a crossing path.
+    pub fn project(&self, position: Vec3, from_distance: f32) -> f32 {
+        let mut closest = (f32::MAX, from_distance);
+        for index in self.segment_at(from_distance)..self.points.len() - 1 {
+            let (from, to) = (self.points[index], self.points[index + 1]);
+            let segment = to - from;
+            let length = self.distances[index + 1] - self.distances[index];
+            let is_last = index == self.points.len() - 2;
+            let mut t = (position - from).dot(segment) / segment.length_squared().max(f32::EPSILON);
+            // Beyond the end, the path continues straight.
+            t = if is_last { t.max(0.) } else { t.clamp(0., 1.) };
+
+            let distance_squared = position.distance_squared(from + segment * t);
+            if distance_squared < closest.0 {
+                closest = (distance_squared, self.distances[index] + t * length);
+            }
+        }
+
+        closest.1
+    }
+
+    fn segment_at(&self, distance: f32) -> usize {
+        self.distances
+            .partition_point(|segment_start| *segment_start <= distance)
+            .saturating_sub(1)
+            .min(self.points.len().saturating_sub(2))
+    }
+}
+
+impl RoadNetwork {
+    /// Samples the straight lanes and the network of the level.
+    ///
+    /// The straight lanes come first, the network paths are offset by the number of lanes.
+    pub fn from_level(level: &LevelDefinition) -> Self {
+        let mut paths: Vec<LanePath> = level
+            .lanes
+            .iter()
+            .map(|lane| {
+                let direction = lane.direction.vector();
+                let z = level.lane_z(lane);
+                let start = -direction * (level.road_length / 2. + DISTANCEUNTILCARSREACHTHEROAD);
+                // Cars leaving the road are despawned at the end of the lane.
+                let end =
+                    direction * (level.road_length / 2. + DISTANCEUNTILCARSREACHTHEROAD * 1.5);
+                LanePath::new(vec![start.with_z(z), end.with_z(z)], vec![])
+            })
+            .collect();
+
+        for (index, path) in level.network.paths.iter().enumerate() {
+            let next = path.next.iter().map(|next| next.index(level)).collect();
+            let mut lane_path = LanePath::from_spline(&path.points, next).unwrap_or_else(|| {
+                warn!("Path {index} of level {} needs two points", level.name);
+                LanePath::new(vec![Vec3::ZERO, Vec3::X], vec![])
+            });
+            lane_path.merge = path.merge;
+            paths.push(lane_path);
+        }
+
+        for exit in &level.network.exits {
+            let Some(from) = paths.get_mut(exit.from.index(level)) else {
+                warn!("Exit of level {} leaves a missing path", level.name);
+                continue;
+            };
+            from.exits.push(Exit {
+                at: exit.at,
+                to: exit.to.index(level),
+                chance: exit.chance,
+            });
+        }
+
+        Self { paths }
+    }
+
+    /// The area on the ground (x, z) covered by the paths.
+    pub fn bounds(&self) -> Rect {
+        self.paths
+            .iter()
+            .flat_map(|path| &path.points)
+            .fold(Rect::EMPTY, |bounds, point| bounds.union_point(point.xz()))
+    }
+}
+
+/// Keeps a car on its path.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct PathFollower {
+    pub path: usize,
+    /// Distance of the car along the path.
+    pub distance: f32,
+    /// Seconds the car has been yielding at a merge.
+    pub waited: f32,
+}
+
+impl PathFollower {
+    pub fn new(path: usize) -> Self {
+        Self {
+            path,
+            distance: 0.,
+            waited: 0.,
+        }
+    }
+
+    fn switch_to(&mut self, path: usize, distance: f32) {
+        self.path = path;
+        self.distance = distance;
+        self.waited = 0.;
+    }
+}
+
+/// The visible surface of a network path. Pertubators can be placed on it.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct RoadPathMesh;
+
+/// Builds the network of the level, the surfaces of its curved paths and the car spawners.
+fn spawn_road_network(
+    mut commands: Commands,
+    level: Res<LevelDefinition>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let network = RoadNetwork::from_level(&level);
+
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.3, 0.3, 0.32),
+        perceptual_roughness: 0.9,
+        ..default()
+    });
+    let lane_count = level.lanes.len();
+    for (index, path) in network.paths.iter().enumerate().skip(lane_count) {
+        commands
+            .spawn((
+                Name::new("Road Path"),
+                RoadPathMesh,
+                StateScoped(Screen::Gameplay),
+                // Slightly lifted to show above the ground, crossing paths do not overlap exactly.
+                Transform::from_xyz(0., 0.01 + 0.001 * (index - lane_count) as f32, 0.),
+                Mesh3d(meshes.add(path_mesh(path))),
+                MeshMaterial3d(material.clone()),
+                Pickable::default(),
+            ))
+            .observe(spawn_pertubator);
+    }
+
+    let lane_spawners = level.lanes.iter().enumerate().map(|(path, lane)| {
+        CarSpawner::new(
+            path,
+            lane.target_velocity,
+            lane.min_spawn_interval,
+            lane.max_spawn_interval,
+        )
+    });
+    let entry_spawners = level.network.entries.iter().map(|entry| {
+        CarSpawner::new(
+            entry.path.index(&level),
+            entry.target_velocity,
+            entry.min_spawn_interval,
+            entry.max_spawn_interval,
+        )
+    });
+    for spawner in lane_spawners.chain(entry_spawners) {
+        let Some(path) = network.paths.get(spawner.path()) else {
+            warn!("Level {} spawns cars on a missing path", level.name);
+            continue;
+        };
+        let position = path.start().with_y(MAXCARHEIGHT / 2.);
+        commands.spawn(create_car_spawner(position, spawner));
+    }
+
+    commands.insert_resource(network);
+}
+
+/// A flat strip of a lane's width along the path.
+fn path_mesh(path: &LanePath) -> Mesh {
+    let mut positions = Vec::with_capacity(path.points.len() * 2);
+    let mut uvs = Vec::with_capacity(path.points.len() * 2);
+    for (index, point) in path.points.iter().enumerate() {
+        let before = path.points[index.saturating_sub(1)];
+        let after = path.points[(index + 1).min(path.points.len() - 1)];
+        let side = Vec3::Y.cross(after - before).normalize_or_zero() * LANEWIDTH / 2.;
+        let v = path.distances[index] / LANEWIDTH;
+
+        positions.push(*point + side);
+        positions.push(*point - side);
+        uvs.push([0., v]);
+        uvs.push([1., v]);
+    }
+
+    let mut indices = Vec::with_capacity(path.points.len() * 6);
+    for index in 0..path.points.len().saturating_sub(1) as u32 {
+        let (left, right) = (2 * index, 2 * index + 1);
+        indices.extend([left, right, left + 2, right, right + 2, left + 2]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 1., 0.]; positions.len()])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Steers the intact cars along their paths, onto exits and the next paths, and despawns them at
+/// the end of the network.
+fn follow_paths(
+    mut commands: Commands,
+    mut cars: Query<(Entity, &mut Car, &mut PathFollower, &Transform), Without<Wrecked>>,
+    network: Res<RoadNetwork>,
+    tuning: Res<GameTuning>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+) {
+    // Where the cars were at the start of the tick, to look for gaps when merging.
+    let traffic: Vec<(usize, f32)> = cars
+        .iter()
+        .map(|(_, _, follower, _)| (follower.path, follower.distance))
+        .collect();
+
+    for (entity, mut car, mut follower, transform) in &mut cars {
+        let position = transform.translation.with_y(0.);
+        let Some(mut path) = network.paths.get(follower.path) else {
+            continue;
+        };
+        let previous_distance = follower.distance;
+        follower.distance = path.project(position, previous_distance);
+
+        let taken_exit = path
+            .exits
+            .iter()
+            .find(|exit| {
+                (previous_distance..follower.distance).contains(&exit.at)
+                    && rng.r#gen::<f32>() < exit.chance
+            })
+            .map(|exit| exit.to);
+        if let Some((to, exit_path)) =
+            taken_exit.and_then(|to| network.paths.get(to).map(|exit_path| (to, exit_path)))
+        {
+            follower.switch_to(to, exit_path.project(position, 0.));
+            path = exit_path;
+        }
+
+        if follower.distance >= path.length() {
+            if path.next.is_empty() {
+                // The car left the network.
+                commands.entity(entity).despawn();
+                continue;
+            }
+
+            let next = path.next[rng.gen_range(0..path.next.len())];
+            if let Some(next_path) = network.paths.get(next) {
+                follower.switch_to(next, next_path.project(position, 0.));
+                path = next_path;
+            }
+        }
+
+        // Close to the end of a merging path, wait for a gap in the traffic on the next paths.
+        let yielding = path.merge
+            && path.length() - follower.distance < MERGE_ZONE
+            && follower.waited < tuning.merge_patience
+            && path.next.iter().any(|next| {
+                let Some(next_path) = network.paths.get(*next) else {
+                    return false;
+                };
+                let distance = next_path.project(position, 0.);
+                traffic.iter().any(|(other_path, other_distance)| {
+                    other_path == next && (other_distance - distance).abs() < tuning.merge_gap
+                })
+            });
+        if yielding {
+            follower.waited += time.delta_secs();
+        }
+        car.set_yielding(yielding);
+
+        let target = path.position_at(follower.distance + PATH_LOOKAHEAD);
+        let direction = (target - position)
+            .with_y(0.)
+            .normalize_or(path.direction_at(follower.distance));
+        car.steer(direction);
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{
+    level::SelectedLevel,
+    pertubator::{Money, Pertubator, UnlockedPertubators},
+    points_money::HighScore,
+    replay::ReplayPlayback,
+    road::SeparatorDamage,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<RoundSeed>();
+    app.register_type::<RoundTick>();
+    app.register_type::<PhysicsRate>();
+    app.init_resource::<RoundSeed>();
+    app.init_resource::<RoundTick>();
+    app.init_resource::<PhysicsRate>();
+    app.insert_resource(Time::<Fixed>::from_hz(PhysicsRate::default().0));
+    app.insert_resource(GameRng::from_seed(0));
+
+    app.add_systems(OnEnter(Screen::Gameplay), start_round);
+    app.add_systems(
+        FixedUpdate,
+        tick_round
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The seed of the current round.
+///
+/// Every random decision of the simulation is drawn from [`GameRng`], which is reseeded with this
+/// value at the start of each round.
+#[derive(Debug, Default, Clone, Copy, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct RoundSeed(pub u64);
+
+/// Seeded random number generator for everything that influences the simulation.
+///
+/// Use `rand::thread_rng` only for purely cosmetic randomness.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(StdRng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+/// Number of fixed updates since the start of the current round.
+#[derive(Debug, Default, Clone, Copy, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct RoundTick(pub u64);
+
+/// Rate of the physics and gameplay simulation in Hz, as chosen in the settings.
+///
+/// Rendered transforms are interpolated between the fixed steps, so lower rates only cost
+/// accuracy, not smoothness. A changed rate is used from the next round on, so the running
+/// round stays replayable.
+#[derive(Debug, Clone, Copy, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PhysicsRate(pub f64);
+
+impl PhysicsRate {
+    /// Rates selectable in the settings.
+    pub const OPTIONS: [f64; 3] = [30., 60., 120.];
+}
+
+impl Default for PhysicsRate {
+    fn default() -> Self {
+        Self(Self::OPTIONS[0])
+    }
+}
+
+/// Everything needed to start a round in the exact same state again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundConfig {
+    pub seed: u64,
+    /// Rate of the fixed timestep the round was simulated with.
+    pub fixed_hz: f64,
+    pub money: i32,
+    pub score: f32,
+    pub unlocked: Vec<Pertubator>,
+    /// Path of the level the round was played on. The first level, if empty.
+    #[serde(default)]
+    pub level: String,
+    #[serde(default)]
+    pub separator_damage: SeparatorDamage,
+}
+
+impl RoundConfig {
+    /// Captures the configuration of the round that is about to start.
+    pub fn capture(
+        seed: &RoundSeed,
+        fixed_time: &Time<Fixed>,
+        money: &Money,
+        high_score: &HighScore,
+        unlocked: &UnlockedPertubators,
+        level: &SelectedLevel,
+        separator_damage: &SeparatorDamage,
+    ) -> Self {
+        Self {
+            seed: seed.0,
+            fixed_hz: 1. / fixed_time.timestep().as_secs_f64(),
+            money: money.0,
+            score: high_score.get(),
+            unlocked: unlocked.0.clone(),
+            level: level.path().into(),
+            separator_damage: separator_damage.clone(),
+        }
+    }
+
+    /// Puts the world into the state described by this configuration.
+    pub fn apply(
+        &self,
+        seed: &mut RoundSeed,
+        fixed_time: &mut Time<Fixed>,
+        money: &mut Money,
+        high_score: &mut HighScore,
+        unlocked: &mut UnlockedPertubators,
+        level: &mut SelectedLevel,
+        separator_damage: &mut SeparatorDamage,
+    ) {
+        seed.0 = self.seed;
+        fixed_time.set_timestep_hz(self.fixed_hz);
+        money.0 = self.money;
+        high_score.0 = self.score;
+        unlocked.0 = self.unlocked.clone();
+        *level = self.selected_level();
+        *separator_damage = self.separator_damage.clone();
+    }
+
+    pub fn selected_level(&self) -> SelectedLevel {
+        SelectedLevel::from_path(&self.level).unwrap_or_default()
+    }
+}
+
+/// Chooses the seed and physics rate of the new round and resets the tick counter.
+///
+/// Systems drawing from [`GameRng`] on `OnEnter(Screen::Gameplay)` have to run after this one.
+pub(super) fn start_round(
+    mut seed: ResMut<RoundSeed>,
+    mut rng: ResMut<GameRng>,
+    mut tick: ResMut<RoundTick>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut money: ResMut<Money>,
+    mut high_score: ResMut<HighScore>,
+    mut unlocked: ResMut<UnlockedPertubators>,
+    mut level: ResMut<SelectedLevel>,
+    mut separator_damage: ResMut<SeparatorDamage>,
+    physics_rate: Res<PhysicsRate>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    if let Some(playback) = playback {
+        playback.replay.config.apply(
+            &mut seed,
+            &mut fixed_time,
+            &mut money,
+            &mut high_score,
+            &mut unlocked,
+            &mut level,
+            &mut separator_damage,
+        );
+    } else {
+        seed.0 = rand::thread_rng().r#gen();
+        fixed_time.set_timestep_hz(physics_rate.0);
+    }
+
+    *rng = GameRng::from_seed(seed.0);
+    tick.0 = 0;
+}
+
+fn tick_round(mut tick: ResMut<RoundTick>) {
+    tick.0 += 1;
+}
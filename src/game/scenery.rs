@@ -0,0 +1,366 @@
+//! Props around the road: trees, fences, signs and buildings.
+//!
+//! Fences and signs line the road edges, trees and buildings are scattered further out by the
+//! density of the level's scenery. Everything keeps clear of the road and of the paths cars drive
+//! on. Fences and signs are destructible: they stand still until a car crashes into them and then
+//! fly off as physics objects.
+
+use std::f32::consts::{PI, TAU};
+
+use avian3d::prelude::*;
+use bevy::{
+    color::palettes::css::{BEIGE, DARK_GREEN, GRAY, LIGHT_GRAY, SADDLE_BROWN, STEEL_BLUE, WHITE},
+    ecs::spawn::SpawnWith,
+    prelude::*,
+};
+use rand::Rng;
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{
+    consts::LANEWIDTH,
+    crash::{CarCrash, CarCrashable, PreStepVelocity},
+    layers::GameLayer,
+    level::{LevelDefinition, PropKind, generate_endless_highway},
+    road_network::RoadNetwork,
+    round::{GameRng, start_round},
+    world::spawn_grass,
+};
+
+/// Free space between the road edge and any prop.
+const ROAD_CLEARANCE: f32 = 1.;
+/// Free space between the center of a path and any prop.
+const PATH_CLEARANCE: f32 = LANEWIDTH;
+/// Distance of the fences from the road edge.
+const FENCE_OFFSET: f32 = 1.5;
+const SIGN_OFFSET: f32 = 3.;
+const SIGN_SPACING: f32 = 30.;
+/// Space kept free around the props which must not overlap.
+const SIGN_RADIUS: f32 = 1.;
+const TREE_RADIUS: f32 = 1.5;
+const BUILDING_RADIUS: f32 = 8.;
+/// Width of the bands along the road trees and buildings are scattered in.
+const TREE_BAND: (f32, f32) = (4., 40.);
+const BUILDING_BAND: (f32, f32) = (20., 60.);
+/// Extent of the scenery along the road.
+const SCENERY_HALF_LENGTH: f32 = 75.;
+/// Props per square unit at full density.
+const TREES_PER_AREA: f32 = 0.004;
+const BUILDINGS_PER_AREA: f32 = 0.0004;
+/// Share of the crashing car's velocity a smashed prop flies off with.
+const SMASH_VELOCITY_FACTOR: f32 = 0.8;
+/// Upwards velocity of a smashed prop in m/s.
+const SMASH_LIFT: f32 = 3.;
+/// Spin of a smashed prop toppling over, per m/s of the car's velocity.
+const SMASH_SPIN: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<SceneryProp>();
+    app.register_type::<Destructible>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_scenery
+            .after(start_round)
+            .after(generate_endless_highway)
+            // Both draw from the `GameRng`, so their order has to be fixed.
+            .after(spawn_grass),
+    );
+    app.add_systems(
+        FixedUpdate,
+        smash_props
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct SceneryProp(pub PropKind);
+
+/// A prop which breaks loose when a car crashes into it.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct Destructible;
+
+/// Meshes and materials shared by all props of a round.
+#[derive(Clone)]
+struct PropMeshes {
+    trunk: Handle<Mesh>,
+    crown: Handle<Mesh>,
+    fence: Handle<Mesh>,
+    pole: Handle<Mesh>,
+    sign: Handle<Mesh>,
+    building: Handle<Mesh>,
+    wood: Handle<StandardMaterial>,
+    leaves: Handle<StandardMaterial>,
+    fence_paint: Handle<StandardMaterial>,
+    metal: Handle<StandardMaterial>,
+    sign_paint: Handle<StandardMaterial>,
+    walls: [Handle<StandardMaterial>; 3],
+}
+
+impl PropMeshes {
+    fn new(meshes: &mut Assets<Mesh>, materials: &mut Assets<StandardMaterial>) -> Self {
+        Self {
+            trunk: meshes.add(Cylinder::new(0.3, 2.)),
+            crown: meshes.add(Cone::new(1.5, 3.)),
+            fence: meshes.add(Cuboid::new(LANEWIDTH, 1., 0.15)),
+            pole: meshes.add(Cylinder::new(0.08, 2.5)),
+            sign: meshes.add(Cuboid::new(1.2, 0.8, 0.05)),
+            // Scaled to the size of each building.
+            building: meshes.add(Cuboid::new(1., 1., 1.)),
+            wood: materials.add(Color::from(SADDLE_BROWN)),
+            leaves: materials.add(Color::from(DARK_GREEN)),
+            fence_paint: materials.add(Color::from(WHITE)),
+            metal: materials.add(Color::from(GRAY)),
+            sign_paint: materials.add(Color::from(STEEL_BLUE)),
+            walls: [
+                materials.add(Color::from(BEIGE)),
+                materials.add(Color::from(LIGHT_GRAY)),
+                materials.add(Color::from(GRAY)),
+            ],
+        }
+    }
+}
+
+/// A prop to spawn, rolled up front.
+struct Placement {
+    kind: PropKind,
+    translation: Vec3,
+    rotation: Quat,
+    /// Size of buildings.
+    size: Vec3,
+    variant: usize,
+}
+
+impl Placement {
+    fn new(kind: PropKind, position: Vec2, rotation: Quat) -> Self {
+        Self {
+            kind,
+            translation: Vec3::new(position.x, 0., position.y),
+            rotation,
+            size: Vec3::new(8., 6., 8.),
+            variant: 0,
+        }
+    }
+}
+
+fn spawn_scenery(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    level: Res<LevelDefinition>,
+    mut rng: ResMut<GameRng>,
+) {
+    let network = RoadNetwork::from_level(&level);
+    let half_road_width = level.road_width() / 2.;
+    let half_road_length = level.road_length / 2.;
+
+    // Whether a prop of the given radius keeps clear of the road and the paths.
+    let is_clear = |position: Vec2, radius: f32| {
+        let on_road = position.x.abs() < half_road_length + radius + ROAD_CLEARANCE
+            && position.y.abs() < half_road_width + radius + ROAD_CLEARANCE;
+        let point = Vec3::new(position.x, 0., position.y);
+        let on_path = network.paths.iter().any(|path| {
+            let closest = path.position_at(path.project(point, 0.));
+            closest.distance(point) < radius + PATH_CLEARANCE
+        });
+        !on_road && !on_path
+    };
+
+    // Grass is spawned with the ground.
+    let mut placements: Vec<Placement> = level
+        .scenery
+        .props
+        .iter()
+        .filter(|prop| !matches!(prop.kind, PropKind::Grass | PropKind::GrassLarge))
+        .map(|prop| Placement::new(prop.kind, prop.position, Quat::IDENTITY))
+        .collect();
+    // Positions and radii of the placed trees, signs and buildings, which must not overlap.
+    let mut taken: Vec<(Vec2, f32)> = vec![];
+
+    for side in [-1., 1.] {
+        // Fences and signs face the road.
+        let facing = Quat::from_rotation_y(if side < 0. { 0. } else { PI });
+
+        // Fences leave gaps where paths leave the road.
+        if level.scenery.fences {
+            let segments = (level.road_length / LANEWIDTH).round() as i32;
+            for segment in 0..segments {
+                let x = -half_road_length + (segment as f32 + 0.5) * LANEWIDTH;
+                let position = Vec2::new(x, side * (half_road_width + FENCE_OFFSET));
+                if is_clear(position, 0.) {
+                    placements.push(Placement::new(PropKind::Fence, position, facing));
+                }
+            }
+        }
+
+        // Signs stand at the right side of the road, in driving direction.
+        let signs = (level.road_length / SIGN_SPACING) as i32;
+        for sign in 0..signs {
+            let x = -side * (-half_road_length + (sign as f32 + 0.5) * SIGN_SPACING);
+            let position = Vec2::new(x, side * (half_road_width + SIGN_OFFSET));
+            if is_clear(position, 0.) {
+                placements.push(Placement::new(PropKind::Sign, position, facing));
+                taken.push((position, SIGN_RADIUS));
+            }
+        }
+
+        // Buildings first, the trees fill the gaps.
+        for (kind, band, per_area, radius) in [
+            (
+                PropKind::Building,
+                BUILDING_BAND,
+                BUILDINGS_PER_AREA,
+                BUILDING_RADIUS,
+            ),
+            (PropKind::Tree, TREE_BAND, TREES_PER_AREA, TREE_RADIUS),
+        ] {
+            let area = 2. * SCENERY_HALF_LENGTH * (band.1 - band.0);
+            let candidates = (area * per_area * level.scenery.density.max(0.)).round() as u32;
+            for _ in 0..candidates {
+                let position = Vec2::new(
+                    rng.gen_range(-SCENERY_HALF_LENGTH..SCENERY_HALF_LENGTH),
+                    side * (half_road_width + rng.gen_range(band.0..band.1)),
+                );
+                // Roll everything up front, so the number of draws doesn't depend on the checks.
+                let keep = rng.r#gen::<f32>() < level.scenery.density_at(position);
+                let rotation = Quat::from_rotation_y(rng.gen_range(0.0..TAU));
+                let size = Vec3::new(
+                    rng.gen_range(6.0..12.0),
+                    rng.gen_range(4.0..10.0),
+                    rng.gen_range(6.0..12.0),
+                );
+                let variant = rng.gen_range(0..3);
+
+                let overlaps = taken
+                    .iter()
+                    .any(|(other, other_radius)| other.distance(position) < radius + other_radius);
+                if !keep || overlaps || !is_clear(position, radius) {
+                    continue;
+                }
+
+                taken.push((position, radius));
+                placements.push(Placement {
+                    size,
+                    variant,
+                    ..Placement::new(kind, position, rotation)
+                });
+            }
+        }
+    }
+
+    let prop_meshes = PropMeshes::new(&mut meshes, &mut materials);
+    for placement in placements {
+        let mut prop = commands.spawn((
+            Name::new(format!("{:?}", placement.kind)),
+            SceneryProp(placement.kind),
+            StateScoped(Screen::Gameplay),
+            Transform::from_translation(placement.translation).with_rotation(placement.rotation),
+            Visibility::default(),
+            RigidBody::Static,
+            CarCrashable,
+            prop(&placement, &prop_meshes),
+        ));
+        if matches!(placement.kind, PropKind::Fence | PropKind::Sign) {
+            prop.insert(Destructible);
+        }
+    }
+}
+
+/// The body and looks of a prop. The origin is on the ground.
+fn prop(placement: &Placement, prop_meshes: &PropMeshes) -> impl Bundle {
+    let kind = placement.kind;
+    let size = placement.size;
+    let prop_meshes = prop_meshes.clone();
+    let wall = prop_meshes.walls[placement.variant % prop_meshes.walls.len()].clone();
+
+    // Colliders are centered, but props stand on the ground.
+    let (collider, collider_height) = match kind {
+        PropKind::Tree => (Collider::cylinder(0.3, 2.), 1.),
+        PropKind::Fence => (Collider::cuboid(LANEWIDTH, 1., 0.15), 0.5),
+        PropKind::Sign => (Collider::cylinder(0.1, 2.5), 1.25),
+        PropKind::Building => (Collider::cuboid(size.x, size.y, size.z), size.y / 2.),
+        PropKind::Grass | PropKind::GrassLarge => unreachable!("grass is spawned with the ground"),
+    };
+
+    Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+        parent.spawn((
+            Name::new("Prop Collider"),
+            Transform::from_xyz(0., collider_height, 0.),
+            collider,
+            GameLayer::Scenery.collision_layers(),
+        ));
+        match kind {
+            PropKind::Tree => {
+                parent.spawn((
+                    Transform::from_xyz(0., 1., 0.),
+                    Mesh3d(prop_meshes.trunk),
+                    MeshMaterial3d(prop_meshes.wood),
+                ));
+                parent.spawn((
+                    Transform::from_xyz(0., 3.5, 0.),
+                    Mesh3d(prop_meshes.crown),
+                    MeshMaterial3d(prop_meshes.leaves),
+                ));
+            }
+            PropKind::Fence => {
+                parent.spawn((
+                    Transform::from_xyz(0., 0.5, 0.),
+                    Mesh3d(prop_meshes.fence),
+                    MeshMaterial3d(prop_meshes.fence_paint),
+                ));
+            }
+            PropKind::Sign => {
+                parent.spawn((
+                    Transform::from_xyz(0., 1.25, 0.),
+                    Mesh3d(prop_meshes.pole),
+                    MeshMaterial3d(prop_meshes.metal),
+                ));
+                parent.spawn((
+                    Transform::from_xyz(0., 2.5, 0.1),
+                    Mesh3d(prop_meshes.sign),
+                    MeshMaterial3d(prop_meshes.sign_paint),
+                ));
+            }
+            PropKind::Building => {
+                parent.spawn((
+                    Transform::from_xyz(0., size.y / 2., 0.).with_scale(size),
+                    Mesh3d(prop_meshes.building),
+                    MeshMaterial3d(wall),
+                ));
+            }
+            PropKind::Grass | PropKind::GrassLarge => {}
+        }
+    }))
+}
+
+/// Knocks destructible props loose when a car crashes into them.
+fn smash_props(
+    mut commands: Commands,
+    mut car_crashes: EventReader<CarCrash>,
+    props: Query<&RigidBody, With<Destructible>>,
+    cars: Query<&PreStepVelocity>,
+) {
+    for crash in car_crashes.read() {
+        let Ok(rigid_body) = props.get(crash.other) else {
+            continue;
+        };
+        // A prop already flying around is handled by the physics.
+        if rigid_body.is_dynamic() {
+            continue;
+        }
+        let car_velocity = cars
+            .get(crash.car)
+            .map_or(Vec3::ZERO, |velocity| velocity.0);
+
+        commands.entity(crash.other).insert((
+            RigidBody::Dynamic,
+            LinearVelocity(car_velocity * SMASH_VELOCITY_FACTOR + Vec3::Y * SMASH_LIFT),
+            // Topple over in the direction of the car.
+            AngularVelocity(Vec3::Y.cross(car_velocity) * SMASH_SPIN),
+        ));
+    }
+}
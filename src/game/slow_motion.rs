@@ -0,0 +1,166 @@
+//! Slow motion for big chain reactions.
+//!
+//! When enough crashes happen close to each other within a short time, the game slows down for a
+//! moment and the camera zooms in on them. Slowing `Time<Virtual>` also slows the physics, which
+//! runs in the fixed timestep.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{crash::CarCrash, replay::ReplayPlayback, time_scale::TimeScale, tuning::GameTuning};
+
+/// Real seconds the game takes to slow down and to speed up again.
+const SLOW_MOTION_EASE: f32 = 0.3;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<SlowMotion>();
+    app.init_resource::<SlowMotion>();
+    app.add_event::<ChainReaction>();
+    app.init_resource::<RecentCrashPositions>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), reset_slow_motion);
+    app.add_systems(OnExit(Screen::Gameplay), reset_slow_motion);
+    app.add_systems(
+        FixedUpdate,
+        detect_chain_reactions
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        tick_slow_motion
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The current slow motion, if any.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SlowMotion {
+    /// The center of the chain reaction on the ground (x, z).
+    pub focus: Vec2,
+    /// Real seconds since the slow motion started.
+    elapsed: f32,
+    /// Real seconds the slow motion lasts, 0 without one.
+    duration: f32,
+    /// Real seconds until the next slow motion may start.
+    cooldown: f32,
+}
+
+impl SlowMotion {
+    /// How far the slow motion is eased in, from 0 (normal speed) to 1 (slowest).
+    pub fn intensity(&self) -> f32 {
+        if self.duration <= 0. {
+            return 0.;
+        }
+        let ease_in = self.elapsed / SLOW_MOTION_EASE;
+        let ease_out = (self.duration - self.elapsed) / SLOW_MOTION_EASE;
+        let linear = ease_in.min(ease_out).clamp(0., 1.);
+        linear * linear * (3. - 2. * linear)
+    }
+}
+
+/// Sent when a chain reaction starts a slow motion.
+#[derive(Debug, Event)]
+pub struct ChainReaction {
+    /// The center of the chain reaction on the ground (x, z).
+    pub focus: Vec2,
+}
+
+/// Positions of the crashes within the time window of the tuning, with their game time.
+#[derive(Debug, Default, Resource)]
+struct RecentCrashPositions(Vec<(Duration, Vec2)>);
+
+fn reset_slow_motion(
+    mut slow_motion: ResMut<SlowMotion>,
+    mut recent_crashes: ResMut<RecentCrashPositions>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    *slow_motion = SlowMotion::default();
+    recent_crashes.0.clear();
+    time_scale.slow_motion = 1.;
+}
+
+/// Starts a slow motion when enough crashes cluster around the latest one.
+fn detect_chain_reactions(
+    mut car_crashes: EventReader<CarCrash>,
+    mut recent_crashes: ResMut<RecentCrashPositions>,
+    mut slow_motion: ResMut<SlowMotion>,
+    mut chain_reactions: EventWriter<ChainReaction>,
+    transforms: Query<&Transform>,
+    playback: Option<Res<ReplayPlayback>>,
+    tuning: Res<GameTuning>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    let window = Duration::from_secs_f32(tuning.slow_motion_window);
+    recent_crashes
+        .0
+        .retain(|(crash_time, _)| now.saturating_sub(*crash_time) < window);
+
+    for car_crash in car_crashes.read() {
+        let Ok(transform) = transforms.get(car_crash.car) else {
+            continue;
+        };
+        let position = transform.translation.xz();
+        recent_crashes.0.push((now, position));
+
+        let ready = slow_motion.duration <= 0. && slow_motion.cooldown <= 0.;
+        // Fast-forwarding replays skip the show.
+        let seeking = playback
+            .as_ref()
+            .is_some_and(|playback| playback.is_seeking());
+        if !ready || seeking {
+            continue;
+        }
+
+        let cluster: Vec<Vec2> = recent_crashes
+            .0
+            .iter()
+            .map(|(_, other)| *other)
+            .filter(|other| other.distance(position) < tuning.slow_motion_radius)
+            .collect();
+        if cluster.len() < tuning.slow_motion_crashes as usize {
+            continue;
+        }
+
+        let focus = cluster.iter().sum::<Vec2>() / cluster.len() as f32;
+        *slow_motion = SlowMotion {
+            focus,
+            elapsed: 0.,
+            duration: tuning.slow_motion_seconds,
+            cooldown: 0.,
+        };
+        chain_reactions.write(ChainReaction { focus });
+    }
+}
+
+/// Runs the slow motion in real time and slows the game down accordingly.
+fn tick_slow_motion(
+    mut slow_motion: ResMut<SlowMotion>,
+    mut time_scale: ResMut<TimeScale>,
+    tuning: Res<GameTuning>,
+    time: Res<Time<Real>>,
+) {
+    let delta = time.delta_secs();
+    if slow_motion.duration <= 0. {
+        slow_motion.cooldown = (slow_motion.cooldown - delta).max(0.);
+        return;
+    }
+
+    slow_motion.elapsed += delta;
+    if slow_motion.elapsed >= slow_motion.duration {
+        slow_motion.duration = 0.;
+        slow_motion.cooldown = tuning.slow_motion_cooldown;
+        time_scale.slow_motion = 1.;
+        return;
+    }
+
+    time_scale.slow_motion = 1_f32.lerp(tuning.slow_motion_speed, slow_motion.intensity());
+}
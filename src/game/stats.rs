@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{
+    car::Wrecked,
+    crash::CarCrash,
+    pertubator::{Pertubator, PertubatorTriggered, PlacePertubator},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RoundStats>();
+
+    app.add_systems(OnEnter(Screen::Gameplay), reset_round_stats);
+    app.add_systems(
+        FixedUpdate,
+        (
+            count_placements,
+            count_triggers,
+            count_crashes,
+            count_wrecks,
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Statistics of the current round.
+#[derive(Debug, Default, Clone, Resource, Serialize)]
+pub struct RoundStats {
+    pub crashes: u32,
+    pub wrecked_cars: u32,
+    /// In the order the pertubators were first placed.
+    pub pertubators: Vec<PertubatorStats>,
+}
+
+/// Statistics of all pertubators of a kind.
+#[derive(Debug, Clone, Serialize)]
+pub struct PertubatorStats {
+    pub pertubator: Pertubator,
+    pub placed: u32,
+    pub triggered: u32,
+    /// Cars wrecked directly by the pertubator.
+    pub wrecks: u32,
+}
+
+impl RoundStats {
+    fn pertubator_mut(&mut self, pertubator: Pertubator) -> &mut PertubatorStats {
+        let index = match self
+            .pertubators
+            .iter()
+            .position(|stats| stats.pertubator == pertubator)
+        {
+            Some(index) => index,
+            None => {
+                self.pertubators.push(PertubatorStats {
+                    pertubator,
+                    placed: 0,
+                    triggered: 0,
+                    wrecks: 0,
+                });
+                self.pertubators.len() - 1
+            }
+        };
+
+        &mut self.pertubators[index]
+    }
+}
+
+fn reset_round_stats(mut stats: ResMut<RoundStats>) {
+    *stats = RoundStats::default();
+}
+
+fn count_placements(mut stats: ResMut<RoundStats>, mut placements: EventReader<PlacePertubator>) {
+    for placement in placements.read() {
+        stats.pertubator_mut(placement.pertubator).placed += 1;
+    }
+}
+
+fn count_triggers(mut stats: ResMut<RoundStats>, mut triggers: EventReader<PertubatorTriggered>) {
+    for trigger in triggers.read() {
+        let pertubator_stats = stats.pertubator_mut(trigger.pertubator);
+        pertubator_stats.triggered += 1;
+        pertubator_stats.wrecks += trigger.wrecks;
+    }
+}
+
+fn count_crashes(mut stats: ResMut<RoundStats>, mut car_crashes: EventReader<CarCrash>) {
+    stats.crashes += car_crashes.read().count() as u32;
+}
+
+fn count_wrecks(mut stats: ResMut<RoundStats>, wrecked_cars: Query<(), Added<Wrecked>>) {
+    stats.wrecked_cars += wrecked_cars.iter().count() as u32;
+}
@@ -0,0 +1,120 @@
+//! Road surfaces like gravel and ice, changing how cars drive over them.
+//!
+//! Each car knows the surface below it. Its wheels grip according to that surface, and a rolling
+//! resistance slows it down.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{AppSystems, PausableSystems, screens::Screen};
+
+use super::{
+    car::{Car, set_friction},
+    car_colliders::WheelCollider,
+    level::{LevelDefinition, Surface, generate_endless_highway},
+    pertubator::{Nailed, Soaped},
+    tuning::GameTuning,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<OnSurface>();
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_surface_zones.after(generate_endless_highway),
+    );
+    app.add_systems(
+        FixedUpdate,
+        (track_surfaces, apply_rolling_resistance)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// The surface a car is on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct OnSurface(pub Surface);
+
+/// Updates the surface below each car, and the friction of its wheels when it changes.
+fn track_surfaces(
+    mut cars: Query<(&Transform, &mut OnSurface, &Children)>,
+    mut wheels: Query<(&mut Friction, Has<Soaped>, Has<Nailed>), With<WheelCollider>>,
+    level: Res<LevelDefinition>,
+    tuning: Res<GameTuning>,
+) {
+    for (transform, mut on_surface, children) in &mut cars {
+        let surface = level.surface_at(transform.translation);
+        if on_surface.0 == surface {
+            continue;
+        }
+        on_surface.0 = surface;
+
+        for child in children {
+            if let Ok((mut friction, is_soaped, is_nailed)) = wheels.get_mut(*child) {
+                set_friction(
+                    &mut friction,
+                    tuning.wheel_friction(is_soaped, is_nailed, surface),
+                );
+            }
+        }
+    }
+}
+
+/// Slows the cars down by the rolling resistance of their surface.
+fn apply_rolling_resistance(
+    mut cars: Query<(&OnSurface, &LinearVelocity, &mut ExternalForce), With<Car>>,
+    tuning: Res<GameTuning>,
+) {
+    for (on_surface, velocity, mut applied_force) in &mut cars {
+        let rolling_resistance = tuning.surface(on_surface.0).rolling_resistance;
+        if rolling_resistance <= 0. {
+            continue;
+        }
+        // Fades out when the car stands still, so it does not wobble back and forth.
+        let rolling_velocity = Vec3::new(velocity.x, 0., velocity.z).clamp_length_max(1.);
+        let new_force = applied_force.force()
+            - rolling_velocity * rolling_resistance * tuning.car_forward_force;
+        applied_force.set_force(new_force);
+    }
+}
+
+/// Marks the surface zones of the level on the road.
+fn spawn_surface_zones(
+    mut commands: Commands,
+    level: Res<LevelDefinition>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for zone in &level.surfaces {
+        let center = (zone.min + zone.max) / 2.;
+        commands.spawn((
+            Name::new(format!("{:?} Surface", zone.surface)),
+            StateScoped(Screen::Gameplay),
+            // Above the road and the path meshes, below the restricted zones.
+            Transform::from_xyz(center.x, 0.055, center.y),
+            Mesh3d(meshes.add(Plane3d::new(Vec3::Y, (zone.max - zone.min) / 2.))),
+            MeshMaterial3d(materials.add(surface_material(zone.surface))),
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+fn surface_material(surface: Surface) -> StandardMaterial {
+    let (base_color, perceptual_roughness, reflectance) = match surface {
+        Surface::Asphalt => (Color::srgba(0.3, 0.3, 0.32, 0.9), 0.9, 0.3),
+        Surface::Gravel => (Color::srgba(0.55, 0.5, 0.4, 0.9), 1., 0.1),
+        Surface::Ice => (Color::srgba(0.75, 0.9, 1., 0.6), 0.05, 0.9),
+        Surface::Oil => (Color::srgba(0.05, 0.05, 0.08, 0.8), 0.1, 0.8),
+        Surface::Wet => (Color::srgba(0.2, 0.25, 0.35, 0.5), 0.05, 0.7),
+    };
+    StandardMaterial {
+        base_color,
+        perceptual_roughness,
+        reflectance,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    }
+}
@@ -0,0 +1,149 @@
+//! The speed of the game clock.
+//!
+//! Several effects change how fast the game runs. Instead of setting the relative speed of
+//! `Time<Virtual>` themselves, they set their own factor of the [`TimeScale`], so they combine
+//! instead of overriding each other. The simulation stays the same per fixed update, only the
+//! number of updates per real second changes, so replays are not affected.
+//!
+//! The sounds of the game play at the speed of the game, the music and the interface keep their
+//! pace.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    audio::{Music, UiSound},
+    screens::Screen,
+};
+
+use super::{crash::CarCrash, replay::ReplayPlayback, tuning::GameTuning};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<TimeScale>();
+    app.init_resource::<TimeScale>();
+    app.init_resource::<HitStop>();
+
+    app.add_systems(OnExit(Screen::Gameplay), end_hit_stop);
+    app.add_systems(
+        FixedUpdate,
+        start_hit_stop
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        tick_hit_stop
+            .in_set(AppSystems::TickTimers)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        PostUpdate,
+        (
+            apply_time_scale.run_if(resource_changed::<TimeScale>),
+            apply_time_scale_to_audio,
+        ),
+    );
+}
+
+/// Factors of the game speed, multiplied into the relative speed of `Time<Virtual>`.
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TimeScale {
+    /// Speed of a replay playback.
+    pub playback: f32,
+    /// Nearly freezes the game for a moment after a very big impact.
+    pub hit_stop: f32,
+    /// Slows the game down during a chain reaction.
+    pub slow_motion: f32,
+    /// Slows the game down while the player uses bullet time.
+    pub bullet_time: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            playback: 1.,
+            hit_stop: 1.,
+            slow_motion: 1.,
+            bullet_time: 1.,
+        }
+    }
+}
+
+impl TimeScale {
+    pub fn speed(&self) -> f32 {
+        self.playback * self.hit_stop * self.slow_motion * self.bullet_time
+    }
+}
+
+fn apply_time_scale(time_scale: Res<TimeScale>, mut virtual_time: ResMut<Time<Virtual>>) {
+    virtual_time.set_relative_speed(time_scale.speed());
+}
+
+/// Sounds started before the time scale changed are updated, new ones start at the right speed.
+/// Changes of their [`PlaybackSettings`] speed, e.g. of the engines, are applied as well.
+fn apply_time_scale_to_audio(
+    time_scale: Res<TimeScale>,
+    sinks: Query<(Ref<PlaybackSettings>, Ref<AudioSink>), (Without<Music>, Without<UiSound>)>,
+    spatial_sinks: Query<
+        (Ref<PlaybackSettings>, Ref<SpatialAudioSink>),
+        (Without<Music>, Without<UiSound>),
+    >,
+) {
+    for (playback, sink) in &sinks {
+        if time_scale.is_changed() || playback.is_changed() || sink.is_added() {
+            sink.set_speed(playback.speed * time_scale.speed());
+        }
+    }
+    for (playback, sink) in &spatial_sinks {
+        if time_scale.is_changed() || playback.is_changed() || sink.is_added() {
+            sink.set_speed(playback.speed * time_scale.speed());
+        }
+    }
+}
+
+/// Real seconds left of the current hit-stop.
+#[derive(Debug, Default, Resource)]
+struct HitStop(f32);
+
+fn start_hit_stop(
+    mut car_crashes: EventReader<CarCrash>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time_scale: ResMut<TimeScale>,
+    playback: Option<Res<ReplayPlayback>>,
+    tuning: Res<GameTuning>,
+) {
+    // Fast-forwarding replays should not stutter.
+    if playback.is_some_and(|playback| playback.is_seeking()) {
+        car_crashes.clear();
+        return;
+    }
+    if car_crashes
+        .read()
+        .any(|car_crash| car_crash.severity >= tuning.hit_stop_severity)
+    {
+        hit_stop.0 = tuning.hit_stop_seconds;
+        time_scale.hit_stop = tuning.hit_stop_speed;
+    }
+}
+
+/// Counts the hit-stop down in real time, the game time barely moves during it.
+fn tick_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    mut time_scale: ResMut<TimeScale>,
+    time: Res<Time<Real>>,
+) {
+    if hit_stop.0 <= 0. {
+        return;
+    }
+    hit_stop.0 -= time.delta_secs();
+    if hit_stop.0 <= 0. {
+        time_scale.hit_stop = 1.;
+    }
+}
+
+fn end_hit_stop(mut hit_stop: ResMut<HitStop>, mut time_scale: ResMut<TimeScale>) {
+    hit_stop.0 = 0.;
+    time_scale.hit_stop = 1.;
+}
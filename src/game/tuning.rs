@@ -0,0 +1,222 @@
+//! Gameplay tuning, loaded from `assets/config/game.tuning.ron`.
+//!
+//! With the `dev_native` feature the file is watched, and changes apply live during a round.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tracking::LoadResource;
+
+use super::level::Surface;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<GameTuning>();
+    app.load_resource_from_path::<GameTuning>("config/game.tuning.ron");
+}
+
+/// Values designers tune the feel of the game with.
+///
+/// Missing fields in the tuning file fall back to their defaults.
+#[derive(Debug, Clone, Resource, Asset, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct GameTuning {
+    /// Force pushing an intact car forward until it reaches its target velocity.
+    pub car_forward_force: f32,
+
+    pub ground_friction: f32,
+    pub car_body_friction: f32,
+    pub wheel_friction_normal: f32,
+    pub wheel_friction_soaped: f32,
+    pub wheel_friction_nailed: f32,
+    pub wheel_friction_soaped_and_nailed: f32,
+
+    /// Crash severity in Ns below which a touch does not count as a crash.
+    pub minimal_crash_severity: f32,
+    /// Crash severity at which the car is marked as wrecked.
+    pub wrecking_threshold: f32,
+    /// Crash severities separating the small, medium and big crash sounds.
+    pub crash_sound_cutoffs: [f32; 2],
+    /// Score per unit of crash severity.
+    pub crash_score_multiplier: f32,
+    /// Impulse applied to the debris flying off a crash.
+    pub debris_impulse: f32,
+
+    /// Crash severity a separator withstands before it breaks.
+    pub separator_health: f32,
+    /// Money per unit of separator damage repaired in the shop.
+    pub separator_repair_cost: f32,
+
+    /// Crash severity shaking the camera the most.
+    pub shake_crash_severity: f32,
+    /// Camera trauma of an explosion in the center of the view, from 0 to 1.
+    pub shake_explosion_trauma: f32,
+    /// Distance from the center of the view beyond which explosions don't shake the camera.
+    pub shake_explosion_range: f32,
+    /// Crash severity from which the game is nearly frozen for a moment.
+    pub hit_stop_severity: f32,
+    /// Length of the hit-stop in real seconds.
+    pub hit_stop_seconds: f32,
+    /// Speed of the game during the hit-stop.
+    pub hit_stop_speed: f32,
+    /// Crashes close to each other which start a slow motion.
+    pub slow_motion_crashes: u32,
+    /// Distance within which crashes count as close to each other.
+    pub slow_motion_radius: f32,
+    /// Game seconds within which crashes count as close to each other.
+    pub slow_motion_window: f32,
+    /// Speed of the game during the slow motion.
+    pub slow_motion_speed: f32,
+    /// Length of the slow motion in real seconds.
+    pub slow_motion_seconds: f32,
+    /// Real seconds after a slow motion before the next one may start.
+    pub slow_motion_cooldown: f32,
+    /// Speed of the game during bullet time.
+    pub bullet_time_speed: f32,
+    /// Real seconds a full bullet time meter lasts.
+    pub bullet_time_seconds: f32,
+    /// Bullet time meter refilled per unit of crash severity, the full meter being 1.
+    pub bullet_time_refill: f32,
+
+    /// Chaos per unit of crash severity, the most chaos being 1.
+    pub chaos_per_severity: f32,
+    /// Seconds in which the chaos of crashes decays to about a third.
+    pub chaos_decay_seconds: f32,
+    /// Chaos per wrecked car on the road.
+    pub chaos_per_wreck: f32,
+    /// Chaos per ongoing explosion.
+    pub chaos_per_explosion: f32,
+    /// Volume of the music without any chaos, the loudest being 1.
+    pub music_calm_volume: f32,
+
+    /// Engines playing at once, the ones closest to the center of the view.
+    pub engine_voices: u32,
+    /// Speed at which the engines rev the highest.
+    pub engine_full_revs_speed: f32,
+    /// Speed below which sliding tires stop screeching.
+    pub screech_min_speed: f32,
+
+    /// Radius in which cars are hit by an exploding barrel.
+    pub barrel_explosion_radius: f32,
+    pub barrel_explosion_strength: f32,
+
+    pub surface_asphalt: SurfaceTuning,
+    pub surface_gravel: SurfaceTuning,
+    pub surface_ice: SurfaceTuning,
+    pub surface_oil: SurfaceTuning,
+    pub surface_wet: SurfaceTuning,
+
+    /// Distance to the traffic on the main lane, below which merging cars yield.
+    pub merge_gap: f32,
+    /// Seconds a merging car yields before forcing its way in.
+    pub merge_patience: f32,
+}
+
+impl Default for GameTuning {
+    fn default() -> Self {
+        Self {
+            car_forward_force: 15.,
+
+            ground_friction: 0.01,
+            car_body_friction: 0.6,
+            wheel_friction_normal: 0.15,
+            wheel_friction_soaped: 0.02,
+            wheel_friction_nailed: 0.6,
+            wheel_friction_soaped_and_nailed: 0.2,
+
+            minimal_crash_severity: 0.5,
+            wrecking_threshold: 2.,
+            crash_sound_cutoffs: [10., 20.],
+            crash_score_multiplier: 100.,
+            debris_impulse: 50.,
+
+            separator_health: 15.,
+            separator_repair_cost: 2.,
+
+            shake_crash_severity: 30.,
+            shake_explosion_trauma: 0.8,
+            shake_explosion_range: 60.,
+            hit_stop_severity: 30.,
+            hit_stop_seconds: 0.08,
+            hit_stop_speed: 0.05,
+            slow_motion_crashes: 4,
+            slow_motion_radius: 20.,
+            slow_motion_window: 1.5,
+            slow_motion_speed: 0.25,
+            slow_motion_seconds: 2.5,
+            slow_motion_cooldown: 8.,
+            bullet_time_speed: 0.3,
+            bullet_time_seconds: 4.,
+            bullet_time_refill: 0.005,
+
+            chaos_per_severity: 0.005,
+            chaos_decay_seconds: 5.,
+            chaos_per_wreck: 0.03,
+            chaos_per_explosion: 0.15,
+            music_calm_volume: 0.6,
+
+            engine_voices: 12,
+            engine_full_revs_speed: 8.,
+            screech_min_speed: 1.,
+
+            barrel_explosion_radius: 15.,
+            barrel_explosion_strength: 50.,
+
+            surface_asphalt: SurfaceTuning {
+                grip: 1.,
+                rolling_resistance: 0.,
+            },
+            surface_gravel: SurfaceTuning {
+                grip: 0.7,
+                rolling_resistance: 0.5,
+            },
+            surface_ice: SurfaceTuning {
+                grip: 0.15,
+                rolling_resistance: 0.,
+            },
+            surface_oil: SurfaceTuning {
+                grip: 0.3,
+                rolling_resistance: 0.05,
+            },
+            surface_wet: SurfaceTuning {
+                grip: 0.6,
+                rolling_resistance: 0.1,
+            },
+
+            merge_gap: 10.,
+            merge_patience: 3.,
+        }
+    }
+}
+
+impl GameTuning {
+    /// Friction of a wheel affected by the given pertubators, on the given surface.
+    pub fn wheel_friction(&self, soaped: bool, nailed: bool, surface: Surface) -> f32 {
+        let friction = match (soaped, nailed) {
+            (true, true) => self.wheel_friction_soaped_and_nailed,
+            (true, false) => self.wheel_friction_soaped,
+            (false, true) => self.wheel_friction_nailed,
+            (false, false) => self.wheel_friction_normal,
+        };
+        friction * self.surface(surface).grip
+    }
+
+    pub fn surface(&self, surface: Surface) -> SurfaceTuning {
+        match surface {
+            Surface::Asphalt => self.surface_asphalt,
+            Surface::Gravel => self.surface_gravel,
+            Surface::Ice => self.surface_ice,
+            Surface::Oil => self.surface_oil,
+            Surface::Wet => self.surface_wet,
+        }
+    }
+}
+
+/// How a road surface affects the cars driving on it.
+#[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
+pub struct SurfaceTuning {
+    /// Factor of the wheel friction.
+    pub grip: f32,
+    /// Force slowing the cars down, as a share of the driving force.
+    pub rolling_resistance: f32,
+}
@@ -2,6 +2,8 @@ use bevy::{color::palettes::css::*, ecs::spawn::SpawnWith, prelude::*};
 
 use crate::{
     game::{
+        bullet_time::BulletTime,
+        level::LevelDefinition,
         pertubator::{ActivePertubator, Pertubator, PertubatorAssets, UnlockedPertubators},
         points_money::HighScore,
     },
@@ -16,23 +18,44 @@ use crate::{
 use super::pertubator::Money;
 
 pub(super) fn plugin(app: &mut App) {
+    app.register_type::<GameUi>();
     app.register_type::<HighScoreUi>();
+    app.register_type::<BulletTimeMeterUi>();
 
-    app.add_systems(Update, (update_highscore, update_money));
+    app.add_systems(
+        Update,
+        (update_highscore, update_money, update_bullet_time_meter),
+    );
 }
 
+/// The root of the HUD during a round.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct GameUi;
+
 pub fn spawn_game_ui(
     mut commands: Commands,
     pertubator_assets: Res<PertubatorAssets>,
     ui_assets: Res<UiAssets>,
     unlocked_pertubators: Res<UnlockedPertubators>,
+    level: Res<LevelDefinition>,
 ) {
+    // Pertubators not allowed in the level are shown like locked ones.
+    let usable_pertubators = UnlockedPertubators(
+        unlocked_pertubators
+            .iter()
+            .copied()
+            .filter(|pertubator| level.allows(*pertubator))
+            .collect(),
+    );
+
     commands.spawn((
         widget::ui_root("UI Root"),
+        GameUi,
         StateScoped(Screen::Gameplay),
         children![
             top_container(&ui_assets),
-            bottom_container(&pertubator_assets, &unlocked_pertubators, &ui_assets),
+            bottom_container(&pertubator_assets, &usable_pertubators, &ui_assets),
         ],
     ));
 }
@@ -136,6 +159,22 @@ fn top_container(ui_assets: &UiAssets) -> impl Bundle {
                 }
             ),
             money(ui_assets),
+            (
+                Node {
+                    width: Val::Px(200.),
+                    ..default()
+                },
+                BackgroundColor(BLACK.with_alpha(0.).into())
+            ),
+            (
+                Text("Bullet Time [Space]: ".into()),
+                TextFont {
+                    font: ui_assets.font.clone(),
+                    font_size: 24.,
+                    ..Default::default()
+                }
+            ),
+            bullet_time_meter(),
             // widget::label("Current Combo?"),
             // widget::label("Achievements")
         ],
@@ -282,3 +321,44 @@ fn money(ui_assets: &UiAssets) -> impl Bundle {
 fn update_money(money: Res<Money>, mut highscore_ui: Single<&mut Text, With<MoneyUi>>) {
     highscore_ui.0 = format!("{}", money.0);
 }
+
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+struct BulletTimeMeterUi;
+
+fn bullet_time_meter() -> impl Bundle {
+    (
+        Name::new("Bullet Time Meter"),
+        Node {
+            width: Val::Px(150.),
+            height: Val::Px(20.),
+            border: UiRect::all(Val::Px(2.)),
+            ..default()
+        },
+        BorderColor(WHITE.into()),
+        children![(
+            Name::new("Bullet Time Fill"),
+            BulletTimeMeterUi,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            BackgroundColor(DEEP_SKY_BLUE.into()),
+        )],
+    )
+}
+
+fn update_bullet_time_meter(
+    bullet_time: Res<BulletTime>,
+    meter_ui: Single<(&mut Node, &mut BackgroundColor), With<BulletTimeMeterUi>>,
+) {
+    let (mut node, mut background) = meter_ui.into_inner();
+    node.width = Val::Percent(bullet_time.meter * 100.);
+    // Lights up while in use.
+    background.0 = if bullet_time.active {
+        AQUA.into()
+    } else {
+        DEEP_SKY_BLUE.into()
+    };
+}
@@ -5,8 +5,9 @@ use crate::{AppSystems, PausableSystems};
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Lifetime>();
 
+    // Lifetimes decide when pertubators disappear, so they are part of the simulation.
     app.add_systems(
-        Update,
+        FixedUpdate,
         tick_lifetime
             .in_set(AppSystems::Update)
             .in_set(PausableSystems),
@@ -3,21 +3,24 @@ use rand::Rng;
 
 use crate::{
     asset_tracking::LoadResource,
-    game::consts::{LANEWIDTH, ROADLENGTH},
+    game::{
+        consts::LANEWIDTH,
+        level::{LevelDefinition, PropKind, generate_endless_highway},
+    },
     screens::Screen,
 };
 
-use super::car::CarCrashable;
+use super::round::{GameRng, start_round};
 
 pub fn plugin(app: &mut App) {
     app.load_resource::<WorldAssets>();
     app.register_type::<WorldAssets>();
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_grass);
-
-    app.register_type::<MusicAssets>();
-    app.load_resource::<MusicAssets>();
-    app.add_systems(OnEnter(Screen::Gameplay), start_game_music);
-    // app.add_systems(Update, swap_game_music);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_grass
+            .after(start_round)
+            .after(generate_endless_highway),
+    );
 }
 
 #[derive(Debug, Resource, Asset, Clone, Reflect)]
@@ -48,51 +51,67 @@ fn grass(
     meshes: &mut Assets<Mesh>,
     materials: &mut Assets<StandardMaterial>,
     world_assets: &WorldAssets,
+    level: &LevelDefinition,
+    rng: &mut GameRng,
 ) -> impl Bundle {
     let grass = world_assets.grass.clone();
     let grass_large = world_assets.grass_large.clone();
 
+    let x = -level.road_length / 2.0;
+    let z = level.road_width() / 2.0;
+
+    // Roll the placement up front, the children are spawned later on.
+    let mut tufts = vec![];
+    let amount = (level.road_length / LANEWIDTH) as i32;
+    let rows = level.scenery.grass_rows as i32;
+    for i in 0..amount {
+        for j in -rows..rows {
+            if j == 0 {
+                continue;
+            }
+            let x_rand = rng.gen_range((-LANEWIDTH / 2.0)..(LANEWIDTH / 2.0));
+            let z_rand = rng.gen_range((-LANEWIDTH / 2.0)..(LANEWIDTH / 2.0));
+            let z_rand_2 = rng.gen_range((-LANEWIDTH / 2.0)..(LANEWIDTH / 2.0));
+
+            let grass_rand = rng.gen_range(0..=1);
+            let scene = if grass_rand == 0 {
+                grass.clone()
+            } else {
+                grass_large.clone()
+            };
+
+            let translation = Vec3::new(
+                x + i as f32 * LANEWIDTH + x_rand,
+                0.,
+                z * j.signum() as f32 + j as f32 * LANEWIDTH + z_rand + z_rand_2,
+            );
+            tufts.push((translation, scene));
+        }
+    }
+
+    // The other props are spawned with the rest of the scenery.
+    for prop in &level.scenery.props {
+        let scene = match prop.kind {
+            PropKind::Grass => grass.clone(),
+            PropKind::GrassLarge => grass_large.clone(),
+            _ => continue,
+        };
+        tufts.push((Vec3::new(prop.position.x, 0., prop.position.y), scene));
+    }
+
     (
         Name::new("Ground"),
         Ground,
-        CarCrashable,
         Transform::from_xyz(0., -0.01, 0.),
         Mesh3d(meshes.add(Plane3d::new(Vec3::Y, GRASS_SIZE).mesh())),
         MeshMaterial3d(materials.add(Color::from(GREEN))),
         Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
-            let rng = &mut rand::thread_rng();
-
-            let x = -ROADLENGTH / 2.0;
-            let z = 5.5 * LANEWIDTH; // Harcoded based on the lane we currently have
-
-            let amount = (ROADLENGTH / LANEWIDTH) as i32;
-            for i in 0..amount {
-                for j in -amount..amount {
-                    if j == 0 {
-                        continue;
-                    }
-                    let x_rand = rng.gen_range((-LANEWIDTH / 2.0)..(LANEWIDTH / 2.0));
-                    let z_rand = rng.gen_range((-LANEWIDTH / 2.0)..(LANEWIDTH / 2.0));
-                    let z_rand_2 = rng.gen_range((-LANEWIDTH / 2.0)..(LANEWIDTH / 2.0));
-
-                    let grass_rand = rng.gen_range(0..=1);
-                    let scene = if grass_rand == 0 {
-                        grass.clone()
-                    } else {
-                        grass_large.clone()
-                    };
-
-                    parent.spawn((
-                        Name::new("Grass"),
-                        Transform::from_xyz(
-                            x + i as f32 * LANEWIDTH + x_rand,
-                            0.,
-                            z * j.signum() as f32 + j as f32 * LANEWIDTH + z_rand + z_rand_2,
-                        )
-                        .with_scale(3. * Vec3::ONE),
-                        SceneRoot(scene),
-                    ));
-                }
+            for (translation, scene) in tufts {
+                parent.spawn((
+                    Name::new("Grass"),
+                    Transform::from_translation(translation).with_scale(3. * Vec3::ONE),
+                    SceneRoot(scene),
+                ));
             }
         })),
     )
@@ -103,41 +122,11 @@ pub fn spawn_grass(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     world_assets: Res<WorldAssets>,
+    level: Res<LevelDefinition>,
+    mut rng: ResMut<GameRng>,
 ) {
     commands.spawn((
         StateScoped(Screen::Gameplay),
-        grass(&mut meshes, &mut materials, &world_assets),
-    ));
-}
-
-#[derive(Resource, Asset, Clone, Reflect)]
-#[reflect(Resource)]
-struct MusicAssets {
-    #[dependency]
-    music: Handle<AudioSource>,
-}
-
-impl FromWorld for MusicAssets {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            music: assets.load("audio/music/FreakyWaves - CrashThemAll.ogg"),
-        }
-    }
-}
-
-#[derive(Component, Reflect, Default)]
-#[reflect(Component)]
-pub struct GameMusic;
-
-fn start_game_music(mut commands: Commands, game_music: Res<MusicAssets>) {
-    commands.spawn((
-        Name::new("Game Music"),
-        StateScoped(Screen::Gameplay),
-        AudioPlayer(game_music.music.clone()),
-        PlaybackSettings::LOOP,
-        GameMusic,
+        grass(&mut meshes, &mut materials, &world_assets, &level, &mut rng),
     ));
 }
-
-// fn swap_game_music(mut commands: Commands, game_music: Res<MusicAssets>) {}
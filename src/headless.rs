@@ -0,0 +1,266 @@
+//! Runs a single round without a window, renderer, audio or UI and prints its results as JSON.
+//!
+//! Start it with `--headless`:
+//!
+//! ```text
+//! bevy_jam6 --headless --seed 42 --seconds 60 --script placements.ron --level levels/junction.level.ron
+//! ```
+//!
+//! The level is one of [`LEVELS`] or `endless`, the first one by default.
+//! The script is a RON list of [`PertubatorPlacement`]s. The round is simulated through the replay
+//! playback, so a script behaves exactly like a replay with the given seed and length.
+
+use std::{path::PathBuf, time::Duration};
+
+use avian3d::prelude::*;
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    asset::AssetMetaCheck,
+    audio::{AudioLoader, AudioPlugin},
+    prelude::*,
+    render::{RenderPlugin, settings::WgpuSettings},
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use serde::Serialize;
+
+use crate::{
+    asset_tracking::{self, ResourceHandles},
+    configure_schedules,
+    game::{
+        self,
+        level::{LEVELS, SelectedLevel},
+        pertubator::{Money, Pertubator, UnlockedPertubators},
+        points_money::HighScore,
+        replay::{PertubatorPlacement, Replay, start_playback},
+        road::SeparatorDamage,
+        round::{RoundConfig, RoundSeed, RoundTick},
+        stats::RoundStats,
+    },
+    screens::Screen,
+};
+
+/// Rate of the fixed timestep used by the headless simulation.
+const HEADLESS_FIXED_HZ: f64 = 30.0;
+
+/// Command line arguments of a headless run.
+#[derive(Debug, Clone, Resource)]
+pub struct HeadlessArgs {
+    seed: u64,
+    seconds: f64,
+    /// Length of the round in fixed timesteps, from the seconds.
+    ticks: u64,
+    script: Option<PathBuf>,
+    level: String,
+}
+
+impl HeadlessArgs {
+    /// Parses the process arguments. Returns `None` if the game should start normally.
+    pub fn from_env() -> Option<Self> {
+        let mut args = std::env::args().skip(1);
+        let mut headless = false;
+        let mut unknown = vec![];
+        let mut parsed = Self {
+            seed: 0,
+            seconds: 60.,
+            ticks: 0,
+            script: None,
+            level: LEVELS[0].into(),
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--seed" => parsed.seed = parse_value(&arg, args.next()),
+                "--seconds" => parsed.seconds = parse_value(&arg, args.next()),
+                "--script" => parsed.script = args.next().map(PathBuf::from),
+                "--level" => parsed.level = args.next().expect("--level expects a level path"),
+                _ => unknown.push(arg),
+            }
+        }
+
+        if !headless {
+            return None;
+        }
+
+        // Logging is not set up yet.
+        for arg in unknown {
+            eprintln!("Ignoring unknown argument {arg}");
+        }
+
+        parsed.ticks = (parsed.seconds * HEADLESS_FIXED_HZ).round() as u64;
+        Some(parsed)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<String>) -> T {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("{arg} expects a number"))
+}
+
+pub struct HeadlessPlugin(pub HeadlessArgs);
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        // `DefaultPlugins` without a window, GPU and audio output. `MinimalPlugins` is not enough:
+        // the colliders of the cars are built from their glTF models, which need the asset, scene
+        // and glTF plugins to load, and those rely on the render plugin for their mesh assets.
+        app.add_plugins(
+            DefaultPlugins
+                .set(AssetPlugin {
+                    meta_check: AssetMetaCheck::Never,
+                    ..default()
+                })
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .disable::<WinitPlugin>()
+                .disable::<AudioPlugin>(),
+        );
+        app.add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO));
+
+        // Audio assets are still loaded as dependencies of the game's asset collections.
+        app.init_asset::<AudioSource>();
+        app.init_asset_loader::<AudioLoader>();
+
+        // Advance exactly one fixed timestep per frame, as fast as possible.
+        let fixed_time = Time::<Fixed>::from_hz(HEADLESS_FIXED_HZ);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(fixed_time.timestep()));
+        app.insert_resource(fixed_time);
+
+        app.add_plugins(PhysicsPlugins::default());
+        app.add_plugins((asset_tracking::plugin, game::plugin));
+        app.init_state::<Screen>();
+        configure_schedules(app);
+
+        app.insert_resource(self.0.clone());
+        app.add_systems(
+            Update,
+            (
+                start_round.run_if(in_state(Screen::Splash).and(all_assets_loaded)),
+                enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
+                finish_round.run_if(in_state(Screen::Gameplay)),
+            ),
+        );
+    }
+}
+
+fn all_assets_loaded(resource_handles: Res<ResourceHandles>) -> bool {
+    resource_handles.is_all_done()
+}
+
+/// Starts the scripted round as a replay playback.
+fn start_round(
+    mut commands: Commands,
+    args: Res<HeadlessArgs>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    seed: Res<RoundSeed>,
+    fixed_time: Res<Time<Fixed>>,
+    money: Res<Money>,
+    high_score: Res<HighScore>,
+    unlocked: Res<UnlockedPertubators>,
+    level: Res<SelectedLevel>,
+    separator_damage: Res<SeparatorDamage>,
+) {
+    let placements = match &args.script {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                ron::from_str::<Vec<PertubatorPlacement>>(&content).map_err(|err| err.to_string())
+            })
+            .unwrap_or_else(|err| panic!("Could not read script {}: {err}", path.display())),
+        None => vec![],
+    };
+
+    if SelectedLevel::from_path(&args.level).is_none() {
+        panic!("Unknown level {}, expected one of {LEVELS:?}", args.level);
+    }
+
+    let replay = Replay {
+        config: RoundConfig {
+            seed: args.seed,
+            fixed_hz: HEADLESS_FIXED_HZ,
+            money: 0,
+            score: 0.,
+            unlocked: vec![
+                Pertubator::Soap,
+                Pertubator::Nails,
+                Pertubator::Spring,
+                Pertubator::Barrel,
+            ],
+            level: args.level.clone(),
+            separator_damage: SeparatorDamage::default(),
+        },
+        placements,
+        length: args.ticks,
+    };
+
+    start_playback(
+        &mut commands,
+        replay,
+        RoundConfig::capture(
+            &seed,
+            &fixed_time,
+            &money,
+            &high_score,
+            &unlocked,
+            &level,
+            &separator_damage,
+        ),
+        &mut next_screen,
+    );
+}
+
+fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+/// Result of a headless run.
+#[derive(Debug, Serialize)]
+struct RoundResult {
+    seed: u64,
+    ticks: u64,
+    score: f32,
+    money: i32,
+    #[serde(flatten)]
+    stats: RoundStats,
+}
+
+fn finish_round(
+    args: Res<HeadlessArgs>,
+    tick: Res<RoundTick>,
+    high_score: Res<HighScore>,
+    money: Res<Money>,
+    stats: Res<RoundStats>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    if tick.0 < args.ticks {
+        return;
+    }
+
+    let result = RoundResult {
+        seed: args.seed,
+        ticks: tick.0,
+        score: high_score.get(),
+        money: money.0,
+        stats: stats.clone(),
+    };
+
+    match serde_json::to_string_pretty(&result) {
+        Ok(json) => println!("{json}"),
+        Err(err) => error!("Could not serialize round result: {err}"),
+    }
+    app_exit.write(AppExit::Success);
+}
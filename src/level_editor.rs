@@ -0,0 +1,633 @@
+//! An editor for the layout of the current level. This plugin is only enabled in dev builds.
+//!
+//! Press F2 during a round to toggle it. Edits change the loaded level asset, so they apply to the
+//! running round where possible, and Enter restarts the round to play-test them. Ctrl+S writes the
+//! level back to its file.
+//!
+//! Tab switches between the tools:
+//! - Tiles: Up/Down select a tile, 1/2/3 make it straight, a border or a separator,
+//!   Insert adds a tile after it, Delete removes it.
+//! - Lanes: Up/Down select an edge between tiles, Insert adds a lane there, Delete removes it,
+//!   D flips its direction, +/- change its target velocity, [/] its spawn interval.
+//! - Zones: Click restricts placing pertubators on a tile-sized square, right-click clears it.
+//! - Surfaces: 1-5 choose asphalt, gravel, ice, oil or wet, click paints a tile-sized square,
+//!   right-click clears it.
+//! - Scenery: 1-6 choose grass, large grass, a tree, a fence, a sign or a building, click drops it,
+//!   right-click removes the closest one.
+
+use bevy::{
+    color::palettes::css::{AQUA, GRAY, GREEN, ORANGE, RED, WHITE, YELLOW},
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+    ui::Val::*,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    game::{
+        consts::LANEWIDTH,
+        level::{
+            LaneDefinition, LaneDirection, LevelAssets, LevelDefinition, PlayTest, PropKind,
+            PropPlacement, RestrictedZone, RoadTile, SelectedLevel, Surface, SurfaceZone,
+        },
+        pertubator::ActivePertubator,
+    },
+    screens::Screen,
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F2;
+
+/// Distance from the cursor within which a prop is removed.
+const PROP_PICK_RADIUS: f32 = 2.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelEditor>();
+
+    app.add_systems(
+        Update,
+        toggle_editor.run_if(in_state(Screen::Gameplay).and(input_just_pressed(TOGGLE_KEY))),
+    );
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_editor_hud.run_if(editor_active),
+    );
+    app.add_systems(
+        Update,
+        (
+            disable_pertubators,
+            switch_tool.run_if(input_just_pressed(KeyCode::Tab)),
+            edit_level,
+            play_test.run_if(input_just_pressed(KeyCode::Enter)),
+            save_level.run_if(input_just_pressed(KeyCode::KeyS)),
+            draw_layout,
+            update_editor_hud,
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay).and(editor_active)),
+    );
+}
+
+#[derive(Debug, Default, Resource)]
+struct LevelEditor {
+    active: bool,
+    tool: EditorTool,
+    /// Selected tile, or selected edge between tiles for the lanes.
+    selection: usize,
+    prop: Option<PropKind>,
+    surface: Surface,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum EditorTool {
+    #[default]
+    Tiles,
+    Lanes,
+    Zones,
+    Surfaces,
+    Scenery,
+}
+
+impl EditorTool {
+    fn next(self) -> Self {
+        match self {
+            EditorTool::Tiles => EditorTool::Lanes,
+            EditorTool::Lanes => EditorTool::Zones,
+            EditorTool::Zones => EditorTool::Surfaces,
+            EditorTool::Surfaces => EditorTool::Scenery,
+            EditorTool::Scenery => EditorTool::Tiles,
+        }
+    }
+}
+
+#[derive(Debug, Default, Component)]
+struct EditorHud;
+
+fn editor_active(editor: Res<LevelEditor>) -> bool {
+    editor.active
+}
+
+fn toggle_editor(
+    mut commands: Commands,
+    mut editor: ResMut<LevelEditor>,
+    huds: Query<Entity, With<EditorHud>>,
+) {
+    editor.active = !editor.active;
+    // Every round while the editor is open play-tests the level.
+    if editor.active {
+        commands.init_resource::<PlayTest>();
+        commands.run_system_cached(spawn_editor_hud);
+    } else {
+        commands.remove_resource::<PlayTest>();
+        for hud in &huds {
+            commands.entity(hud).despawn();
+        }
+    }
+}
+
+fn spawn_editor_hud(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Level Editor HUD"),
+        EditorHud,
+        StateScoped(Screen::Gameplay),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Px(10.0),
+            left: Px(10.0),
+            padding: UiRect::all(Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        GlobalZIndex(3),
+        Pickable::IGNORE,
+        children![(Text::default(), TextFont::from_font_size(14.0))],
+    ));
+}
+
+/// Clicks edit the level instead of placing pertubators.
+fn disable_pertubators(mut active_pertubator: ResMut<ActivePertubator>) {
+    if active_pertubator.0.is_some() {
+        active_pertubator.0 = None;
+    }
+}
+
+fn switch_tool(mut editor: ResMut<LevelEditor>) {
+    editor.tool = editor.tool.next();
+    editor.selection = 0;
+}
+
+/// Applies the input of the current tool to the loaded level asset.
+fn edit_level(
+    mut editor: ResMut<LevelEditor>,
+    selected: Res<SelectedLevel>,
+    level_assets: Res<LevelAssets>,
+    mut levels: ResMut<Assets<LevelDefinition>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera3d>>,
+) {
+    let SelectedLevel::Level(index) = *selected else {
+        return;
+    };
+    let handle = &level_assets.levels[index];
+
+    // Every mutable access marks the asset as modified, so the level is only borrowed mutably to
+    // apply an edit.
+    let Some(level) = levels.get(handle) else {
+        return;
+    };
+
+    let selectable = match editor.tool {
+        EditorTool::Tiles => level.tiles.len().saturating_sub(1),
+        EditorTool::Lanes => level.tiles.len(),
+        EditorTool::Zones | EditorTool::Surfaces | EditorTool::Scenery => 0,
+    };
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        editor.selection = (editor.selection + 1).min(selectable);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        editor.selection = editor.selection.saturating_sub(1);
+    }
+    editor.selection = editor.selection.min(selectable);
+
+    // The point on the ground below the cursor, also beside the road.
+    let (camera, camera_transform) = *camera;
+    let cursor = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor).ok())
+        .and_then(|ray| {
+            ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))
+                .map(|distance| ray.get_point(distance).xz())
+        });
+
+    let edit = match editor.tool {
+        EditorTool::Tiles => edit_tiles(level, editor.selection, &keys),
+        EditorTool::Lanes => edit_lanes(level, editor.selection, &keys),
+        EditorTool::Zones => cursor.and_then(|cursor| paint_zones(level, cursor, &mouse)),
+        EditorTool::Surfaces => {
+            let keys_and_surfaces = [
+                KeyCode::Digit1,
+                KeyCode::Digit2,
+                KeyCode::Digit3,
+                KeyCode::Digit4,
+                KeyCode::Digit5,
+            ]
+            .into_iter()
+            .zip(Surface::ALL);
+            for (key, surface) in keys_and_surfaces {
+                if keys.just_pressed(key) {
+                    editor.surface = surface;
+                }
+            }
+            cursor.and_then(|cursor| paint_surfaces(level, editor.surface, cursor, &mouse))
+        }
+        EditorTool::Scenery => {
+            for (key, prop) in [
+                (KeyCode::Digit1, PropKind::Grass),
+                (KeyCode::Digit2, PropKind::GrassLarge),
+                (KeyCode::Digit3, PropKind::Tree),
+                (KeyCode::Digit4, PropKind::Fence),
+                (KeyCode::Digit5, PropKind::Sign),
+                (KeyCode::Digit6, PropKind::Building),
+            ] {
+                if keys.just_pressed(key) {
+                    editor.prop = Some(prop);
+                }
+            }
+            let kind = editor.prop.unwrap_or(PropKind::Grass);
+            cursor.and_then(|cursor| drop_props(level, kind, cursor, &mouse))
+        }
+    };
+
+    if let Some(edit) = edit {
+        if let Some(level) = levels.get_mut(handle) {
+            edit.apply(level);
+        }
+    }
+}
+
+/// A change of the level, decided on from the input before the level asset is modified.
+enum LevelEdit {
+    SetTile(usize, RoadTile),
+    InsertTile(usize),
+    RemoveTile(usize),
+    AddLane(LaneDefinition),
+    RemoveLane(usize),
+    ReplaceLane(usize, LaneDefinition),
+    AddZone(RestrictedZone),
+    ClearZones(Vec2),
+    PaintSurface(SurfaceZone),
+    ClearSurfaces(Vec2),
+    AddProp(PropPlacement),
+    RemoveProp(usize),
+}
+
+impl LevelEdit {
+    fn apply(self, level: &mut LevelDefinition) {
+        match self {
+            Self::SetTile(index, tile) => level.tiles[index] = tile,
+            // Lanes keep their place on the road when tiles are added or removed before them.
+            Self::InsertTile(after) => {
+                let at = (after + 1).min(level.tiles.len());
+                level.tiles.insert(at, RoadTile::Straight);
+                for lane in &mut level.lanes {
+                    if lane.position > after as f32 {
+                        lane.position += 1.;
+                    }
+                }
+            }
+            Self::RemoveTile(index) => {
+                level.tiles.remove(index);
+                for lane in &mut level.lanes {
+                    if lane.position > index as f32 {
+                        lane.position -= 1.;
+                    }
+                }
+            }
+            Self::AddLane(lane) => level.lanes.push(lane),
+            Self::RemoveLane(index) => {
+                level.lanes.remove(index);
+            }
+            Self::ReplaceLane(index, lane) => level.lanes[index] = lane,
+            Self::AddZone(zone) => level.restricted_zones.push(zone),
+            Self::ClearZones(cursor) => {
+                level.restricted_zones.retain(|zone| !zone.contains(cursor))
+            }
+            // Painting over a tile replaces its surface, painting asphalt only clears it.
+            Self::PaintSurface(zone) => {
+                let center = (zone.min + zone.max) / 2.;
+                level.surfaces.retain(|other| !other.contains(center));
+                if zone.surface != Surface::Asphalt {
+                    level.surfaces.push(zone);
+                }
+            }
+            Self::ClearSurfaces(cursor) => level.surfaces.retain(|zone| !zone.contains(cursor)),
+            Self::AddProp(prop) => level.scenery.props.push(prop),
+            Self::RemoveProp(index) => {
+                level.scenery.props.remove(index);
+            }
+        }
+    }
+}
+
+fn edit_tiles(
+    level: &LevelDefinition,
+    selection: usize,
+    keys: &ButtonInput<KeyCode>,
+) -> Option<LevelEdit> {
+    if let Some(tile) = level.tiles.get(selection) {
+        for (key, new_tile) in [
+            (KeyCode::Digit1, RoadTile::Straight),
+            (KeyCode::Digit2, RoadTile::Border),
+            (KeyCode::Digit3, RoadTile::Separator),
+        ] {
+            if keys.just_pressed(key) && *tile != new_tile {
+                return Some(LevelEdit::SetTile(selection, new_tile));
+            }
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Insert) {
+        return Some(LevelEdit::InsertTile(selection));
+    }
+    if keys.just_pressed(KeyCode::Delete) && selection < level.tiles.len() {
+        return Some(LevelEdit::RemoveTile(selection));
+    }
+    None
+}
+
+fn edit_lanes(
+    level: &LevelDefinition,
+    selection: usize,
+    keys: &ButtonInput<KeyCode>,
+) -> Option<LevelEdit> {
+    let position = selection as f32;
+    let lane_index = level
+        .lanes
+        .iter()
+        .position(|lane| (lane.position - position).abs() < 0.01);
+
+    let Some(lane_index) = lane_index else {
+        return keys.just_pressed(KeyCode::Insert).then(|| {
+            LevelEdit::AddLane(LaneDefinition {
+                position,
+                direction: LaneDirection::LeftToRight,
+                target_velocity: 5.,
+                min_spawn_interval: 2.,
+                max_spawn_interval: 5.,
+            })
+        });
+    };
+
+    if keys.just_pressed(KeyCode::Delete) {
+        return Some(LevelEdit::RemoveLane(lane_index));
+    }
+
+    let mut lane = level.lanes[lane_index].clone();
+    let mut changed = false;
+    if keys.just_pressed(KeyCode::KeyD) {
+        lane.direction = match lane.direction {
+            LaneDirection::LeftToRight => LaneDirection::RightToLeft,
+            LaneDirection::RightToLeft => LaneDirection::LeftToRight,
+        };
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::Equal) {
+        lane.target_velocity += 0.5;
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::Minus) && lane.target_velocity > 0.5 {
+        lane.target_velocity = (lane.target_velocity - 0.5).max(0.5);
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        lane.min_spawn_interval += 0.5;
+        lane.max_spawn_interval += 0.5;
+        changed = true;
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) && lane.min_spawn_interval > 0.5 {
+        lane.min_spawn_interval -= 0.5;
+        lane.max_spawn_interval -= 0.5;
+        changed = true;
+    }
+    changed.then_some(LevelEdit::ReplaceLane(lane_index, lane))
+}
+
+fn paint_zones(
+    level: &LevelDefinition,
+    cursor: Vec2,
+    mouse: &ButtonInput<MouseButton>,
+) -> Option<LevelEdit> {
+    if mouse.just_pressed(MouseButton::Left) {
+        let min = (cursor / LANEWIDTH).floor() * LANEWIDTH;
+        let zone = RestrictedZone {
+            min,
+            max: min + Vec2::splat(LANEWIDTH),
+        };
+        if !level.restricted_zones.contains(&zone) {
+            return Some(LevelEdit::AddZone(zone));
+        }
+    }
+    let clears = level
+        .restricted_zones
+        .iter()
+        .any(|zone| zone.contains(cursor));
+    (mouse.just_pressed(MouseButton::Right) && clears).then_some(LevelEdit::ClearZones(cursor))
+}
+
+fn paint_surfaces(
+    level: &LevelDefinition,
+    surface: Surface,
+    cursor: Vec2,
+    mouse: &ButtonInput<MouseButton>,
+) -> Option<LevelEdit> {
+    if mouse.just_pressed(MouseButton::Left) {
+        let min = (cursor / LANEWIDTH).floor() * LANEWIDTH;
+        let zone = SurfaceZone {
+            min,
+            max: min + Vec2::splat(LANEWIDTH),
+            surface,
+        };
+        let center = min + Vec2::splat(LANEWIDTH / 2.);
+        let current = level
+            .surfaces
+            .iter()
+            .find(|other| other.contains(center))
+            .map_or(Surface::Asphalt, |other| other.surface);
+        if current != surface {
+            return Some(LevelEdit::PaintSurface(zone));
+        }
+    }
+    let clears = level.surfaces.iter().any(|zone| zone.contains(cursor));
+    (mouse.just_pressed(MouseButton::Right) && clears).then_some(LevelEdit::ClearSurfaces(cursor))
+}
+
+fn drop_props(
+    level: &LevelDefinition,
+    kind: PropKind,
+    cursor: Vec2,
+    mouse: &ButtonInput<MouseButton>,
+) -> Option<LevelEdit> {
+    if mouse.just_pressed(MouseButton::Left) {
+        return Some(LevelEdit::AddProp(PropPlacement {
+            kind,
+            position: cursor,
+        }));
+    }
+    if !mouse.just_pressed(MouseButton::Right) {
+        return None;
+    }
+    level
+        .scenery
+        .props
+        .iter()
+        .enumerate()
+        .map(|(index, prop)| (index, prop.position.distance(cursor)))
+        .filter(|(_, distance)| *distance < PROP_PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| LevelEdit::RemoveProp(index))
+}
+
+/// Restarts the round with the edited level.
+fn play_test(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Loading);
+}
+
+/// Writes the edited level back to its file on Ctrl+S.
+fn save_level(
+    selected: Res<SelectedLevel>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<LevelDefinition>>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let SelectedLevel::Level(index) = *selected else {
+        warn!("Generated levels cannot be saved");
+        return;
+    };
+    let Some(level) = levels.get(&level_assets.levels[index]) else {
+        return;
+    };
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let path = std::path::Path::new("assets").join(selected.path());
+        let result = ron::ser::to_string_pretty(level, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|content| std::fs::write(&path, content).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => info!("Saved level to {}", path.display()),
+            Err(err) => error!("Could not save level: {err}"),
+        }
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        let _ = level;
+        warn!("Levels cannot be saved on the web");
+    }
+}
+
+/// Outlines the tiles, lanes, restricted zones, surfaces and props of the current level.
+fn draw_layout(editor: Res<LevelEditor>, level: Res<LevelDefinition>, mut gizmos: Gizmos) {
+    let half_length = level.road_length / 2.;
+    let edge_z = |edge: usize| -level.road_width() / 2. + edge as f32 * LANEWIDTH;
+    let y = 0.1;
+
+    for (index, tile) in level.tiles.iter().enumerate() {
+        let color = if editor.tool == EditorTool::Tiles && editor.selection == index {
+            YELLOW
+        } else {
+            match tile {
+                RoadTile::Straight => GRAY,
+                RoadTile::Border => WHITE,
+                RoadTile::Separator => ORANGE,
+            }
+        };
+        draw_rect(
+            &mut gizmos,
+            Vec2::new(-half_length, edge_z(index)),
+            Vec2::new(half_length, edge_z(index + 1)),
+            y,
+            color,
+        );
+    }
+
+    if editor.tool == EditorTool::Lanes {
+        let z = edge_z(editor.selection);
+        gizmos.line(
+            Vec3::new(-half_length, y, z),
+            Vec3::new(half_length, y, z),
+            YELLOW,
+        );
+    }
+    for lane in &level.lanes {
+        let z = level.lane_z(lane);
+        let direction = lane.direction.vector();
+        gizmos.arrow(
+            (-direction * half_length).with_y(y).with_z(z),
+            (direction * half_length).with_y(y).with_z(z),
+            GREEN,
+        );
+    }
+
+    for zone in &level.restricted_zones {
+        draw_rect(&mut gizmos, zone.min, zone.max, y, RED);
+    }
+    for zone in &level.surfaces {
+        draw_rect(&mut gizmos, zone.min, zone.max, y, AQUA);
+    }
+
+    for prop in &level.scenery.props {
+        gizmos.sphere(Vec3::new(prop.position.x, y, prop.position.y), 0.5, GREEN);
+    }
+}
+
+fn draw_rect(gizmos: &mut Gizmos, min: Vec2, max: Vec2, y: f32, color: impl Into<Color>) {
+    gizmos.linestrip(
+        [
+            Vec3::new(min.x, y, min.y),
+            Vec3::new(max.x, y, min.y),
+            Vec3::new(max.x, y, max.y),
+            Vec3::new(min.x, y, max.y),
+            Vec3::new(min.x, y, min.y),
+        ],
+        color,
+    );
+}
+
+fn update_editor_hud(
+    editor: Res<LevelEditor>,
+    selected: Res<SelectedLevel>,
+    level: Res<LevelDefinition>,
+    huds: Query<&Children, With<EditorHud>>,
+    mut texts: Query<&mut Text>,
+) {
+    let selection = match editor.tool {
+        EditorTool::Tiles => level
+            .tiles
+            .get(editor.selection)
+            .map_or("none".into(), |tile| {
+                format!("tile {}: {tile:?}", editor.selection)
+            }),
+        EditorTool::Lanes => level
+            .lanes
+            .iter()
+            .find(|lane| (lane.position - editor.selection as f32).abs() < 0.01)
+            .map_or(format!("edge {}: no lane", editor.selection), |lane| {
+                format!(
+                    "edge {}: {:?}, {:.1} m/s, every {:.1}-{:.1} s",
+                    editor.selection,
+                    lane.direction,
+                    lane.target_velocity,
+                    lane.min_spawn_interval,
+                    lane.max_spawn_interval
+                )
+            }),
+        EditorTool::Zones => format!("{} zones", level.restricted_zones.len()),
+        EditorTool::Surfaces => format!("{:?}, {} zones", editor.surface, level.surfaces.len()),
+        EditorTool::Scenery => format!(
+            "{:?}, {} props",
+            editor.prop.unwrap_or(PropKind::Grass),
+            level.scenery.props.len()
+        ),
+    };
+    let warning = if *selected == SelectedLevel::Endless {
+        "\nGenerated levels cannot be edited"
+    } else {
+        ""
+    };
+
+    for children in &huds {
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.0 = format!(
+                    "Level editor: {} ({:?} tool)\n{selection}\n\
+                     Tab: tool, Enter: play-test, Ctrl+S: save, F2: close{warning}",
+                    level.name, editor.tool
+                );
+            }
+        }
+    }
+}
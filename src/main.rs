@@ -9,14 +9,24 @@ mod camera;
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod game;
+#[cfg(not(target_family = "wasm"))]
+mod headless;
+#[cfg(feature = "dev")]
+mod level_editor;
 mod menus;
 mod screens;
+mod soundtrack;
 mod theme;
 
 use avian3d::prelude::*;
 use bevy::{asset::AssetMetaCheck, prelude::*};
 
 fn main() -> AppExit {
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(args) = headless::HeadlessArgs::from_env() {
+        return App::new().add_plugins(headless::HeadlessPlugin(args)).run();
+    }
+
     App::new().add_plugins(AppPlugin).run()
 }
 
@@ -48,11 +58,6 @@ impl Plugin for AppPlugin {
         #[cfg(feature = "dev")]
         app.add_plugins((PhysicsDiagnosticsPlugin, PhysicsDiagnosticsUiPlugin));
 
-        /* 20Hz physics and logic for performance */
-        app.insert_resource(Time::<Fixed>::from_hz(30.0));
-
-        // app.insert_resource(DefaultSpatialScale(SpatialScale(Vec3::splat(0.025))));
-
         app.add_plugins(MeshPickingPlugin);
         app.insert_resource(MeshPickingSettings {
             require_markers: true,
@@ -70,31 +75,50 @@ impl Plugin for AppPlugin {
             game::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
+            #[cfg(feature = "dev")]
+            level_editor::plugin,
             menus::plugin,
             screens::plugin,
+            soundtrack::plugin,
             theme::plugin,
         ));
 
-        // Order new `AppSystems` variants by adding them here:
-        app.configure_sets(
-            Update,
-            (
-                AppSystems::TickTimers,
-                AppSystems::RecordInput,
-                AppSystems::Update,
-            )
-                .chain(),
-        );
-
-        // Set up the `Pause` state.
-        app.init_state::<Pause>();
-        app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
+        configure_schedules(app);
     }
 }
 
-/// High-level groupings of systems for the app in the `Update` schedule.
-/// When adding a new variant, make sure to order it in the `configure_sets`
-/// call above.
+/// Orders the app's system sets and sets up pausing.
+///
+/// Shared with the headless runner.
+fn configure_schedules(app: &mut App) {
+    // Order new `AppSystems` variants by adding them here:
+    app.configure_sets(
+        Update,
+        (
+            AppSystems::TickTimers,
+            AppSystems::RecordInput,
+            AppSystems::Update,
+        )
+            .chain(),
+    );
+    app.configure_sets(
+        FixedUpdate,
+        (
+            AppSystems::TickTimers,
+            AppSystems::RecordInput,
+            AppSystems::Update,
+        )
+            .chain(),
+    );
+
+    // Set up the `Pause` state.
+    app.init_state::<Pause>();
+    app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
+    app.configure_sets(FixedUpdate, PausableSystems.run_if(in_state(Pause(false))));
+}
+
+/// High-level groupings of systems for the app in the `Update` and `FixedUpdate` schedules.
+/// When adding a new variant, make sure to order it in [`configure_schedules`].
 #[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 enum AppSystems {
     /// Tick timers.
@@ -5,8 +5,6 @@ use bevy::{
 };
 
 use crate::{
-    asset_tracking::LoadResource,
-    audio::music,
     menus::Menu,
     theme::{prelude::*, widget::UiAssets},
 };
@@ -17,10 +15,6 @@ pub(super) fn plugin(app: &mut App) {
         Update,
         go_back.run_if(in_state(Menu::Credits).and(input_just_pressed(KeyCode::Escape))),
     );
-
-    app.register_type::<CreditsAssets>();
-    app.load_resource::<CreditsAssets>();
-    app.add_systems(OnEnter(Menu::Credits), start_credits_music);
 }
 
 fn spawn_credits_menu(mut commands: Commands, ui_assets: Res<UiAssets>) {
@@ -109,27 +103,3 @@ fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<
 fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Main);
 }
-
-#[derive(Resource, Asset, Clone, Reflect)]
-#[reflect(Resource)]
-pub struct CreditsAssets {
-    #[dependency]
-    pub music: Handle<AudioSource>,
-}
-
-impl FromWorld for CreditsAssets {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            music: assets.load("audio/music/FreakyWaves - CrashThemAll_credits.ogg"),
-        }
-    }
-}
-
-fn start_credits_music(mut commands: Commands, credits_music: Res<CreditsAssets>) {
-    commands.spawn((
-        Name::new("Credits Music"),
-        StateScoped(Menu::Credits),
-        music(credits_music.music.clone()),
-    ));
-}
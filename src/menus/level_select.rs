@@ -0,0 +1,172 @@
+//! The level selection, opened with "Play" in the main menu.
+
+use bevy::{
+    ecs::spawn::SpawnWith, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*,
+};
+
+use crate::{
+    game::level::{LevelAssets, LevelDefinition, LevelRecords, SelectedLevel},
+    menus::Menu,
+    screens::Screen,
+    theme::{prelude::*, widget::UiAssets},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelButton>();
+
+    app.add_systems(OnEnter(Menu::LevelSelect), spawn_level_select_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::LevelSelect).and(input_just_pressed(KeyCode::Escape))),
+    );
+}
+
+/// The button starting a level.
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+struct LevelButton(SelectedLevel);
+
+fn spawn_level_select_menu(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<LevelDefinition>>,
+    records: Res<LevelRecords>,
+) {
+    commands
+        .spawn((
+            widget::ui_root("Level Select Menu"),
+            GlobalZIndex(2),
+            StateScoped(Menu::LevelSelect),
+        ))
+        .with_children(|parent| {
+            parent.spawn(widget::header("Select Level", &ui_assets));
+
+            let mut previous: Option<&LevelDefinition> = None;
+            for (index, handle) in level_assets.levels.iter().enumerate() {
+                let Some(definition) = levels.get(handle) else {
+                    continue;
+                };
+                let level = SelectedLevel::Level(index);
+                let unlocked = records.is_unlocked(level, definition);
+                let status = match (unlocked, previous) {
+                    (true, _) => best_score_status(level, &records),
+                    (false, Some(previous)) => format!(
+                        "Score {:.0} on {} to unlock",
+                        definition.unlock_score, previous.name
+                    ),
+                    (false, None) => "Locked".into(),
+                };
+                parent.spawn(level_entry(
+                    level,
+                    definition.name.clone(),
+                    definition.description.clone(),
+                    status,
+                    unlocked,
+                    &ui_assets,
+                ));
+                previous = Some(definition);
+            }
+
+            parent.spawn(level_entry(
+                SelectedLevel::Endless,
+                "Endless".into(),
+                "A new highway every round, getting busier with your score.".into(),
+                best_score_status(SelectedLevel::Endless, &records),
+                true,
+                &ui_assets,
+            ));
+
+            parent.spawn(widget::button("Back", go_back_on_click, &ui_assets));
+        });
+}
+
+fn best_score_status(level: SelectedLevel, records: &LevelRecords) -> String {
+    match records.best_score(level) {
+        Some(best_score) => format!("Best score: {best_score:.0}"),
+        None => "Not played yet".into(),
+    }
+}
+
+fn level_entry(
+    level: SelectedLevel,
+    name: String,
+    description: String,
+    status: String,
+    unlocked: bool,
+    ui_assets: &UiAssets,
+) -> impl Bundle {
+    let button_ui_assets = ui_assets.clone();
+    let button_or_lock = Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+        if unlocked {
+            parent.spawn((
+                widget::button(name, start_level, &button_ui_assets),
+                LevelButton(level),
+            ));
+        } else {
+            parent.spawn((
+                Name::new("Level Lock"),
+                Node {
+                    width: Px(380.0),
+                    height: Px(80.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![widget::label(format!("{name} (locked)"), &button_ui_assets)],
+            ));
+        }
+    }));
+
+    (
+        Name::new("Level Entry"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(30.0),
+            ..default()
+        },
+        children![
+            (Name::new("Level Start"), Node::default(), button_or_lock),
+            (
+                Name::new("Level Info"),
+                Node {
+                    width: Px(500.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Px(5.0),
+                    ..default()
+                },
+                children![
+                    widget::label(description, ui_assets),
+                    widget::label(status, ui_assets),
+                ],
+            ),
+        ],
+    )
+}
+
+fn start_level(
+    trigger: Trigger<Pointer<Click>>,
+    child_of: Query<&ChildOf>,
+    level_buttons: Query<&LevelButton>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    // The observer sits on the inner button.
+    let Ok(level_button) = child_of
+        .get(trigger.target)
+        .and_then(|child_of| level_buttons.get(child_of.parent()))
+    else {
+        return;
+    };
+
+    *selected_level = level_button.0;
+    next_screen.set(Screen::Loading);
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
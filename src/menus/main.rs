@@ -2,10 +2,19 @@
 
 use bevy::prelude::*;
 
+#[cfg(not(target_family = "wasm"))]
+use crate::game::{
+    level::SelectedLevel,
+    pertubator::{Money, UnlockedPertubators},
+    points_money::HighScore,
+    replay::{load_latest_replay, start_playback},
+    road::SeparatorDamage,
+    round::{RoundConfig, RoundSeed},
+};
+#[cfg(not(target_family = "wasm"))]
+use crate::screens::Screen;
 use crate::{
-    asset_tracking::ResourceHandles,
     menus::Menu,
-    screens::Screen,
     theme::widget::{self, UiAssets},
 };
 
@@ -25,7 +34,8 @@ fn spawn_main_menu(
         #[cfg(not(target_family = "wasm"))]
         children![
             game_title(asset_server),
-            widget::button("Play", enter_loading_or_gameplay_screen, &ui_assets),
+            widget::button("Play", open_level_select_menu, &ui_assets),
+            widget::button("Last replay", watch_latest_replay, &ui_assets),
             widget::button("Settings", open_settings_menu, &ui_assets),
             widget::button("Credits", open_credits_menu, &ui_assets),
             widget::button("Exit", exit_app, &ui_assets),
@@ -33,23 +43,50 @@ fn spawn_main_menu(
         #[cfg(target_family = "wasm")]
         children![
             game_title(asset_server),
-            widget::button("Play", enter_loading_or_gameplay_screen, &ui_assets),
+            widget::button("Play", open_level_select_menu, &ui_assets),
             widget::button("Settings", open_settings_menu, &ui_assets),
             widget::button("Credits", open_credits_menu, &ui_assets),
         ],
     ));
 }
 
-fn enter_loading_or_gameplay_screen(
+fn open_level_select_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::LevelSelect);
+}
+
+/// Plays back the most recently saved replay file.
+#[cfg(not(target_family = "wasm"))]
+fn watch_latest_replay(
     _: Trigger<Pointer<Click>>,
-    resource_handles: Res<ResourceHandles>,
+    mut commands: Commands,
     mut next_screen: ResMut<NextState<Screen>>,
+    seed: Res<RoundSeed>,
+    fixed_time: Res<Time<Fixed>>,
+    money: Res<Money>,
+    high_score: Res<HighScore>,
+    unlocked: Res<UnlockedPertubators>,
+    level: Res<SelectedLevel>,
+    separator_damage: Res<SeparatorDamage>,
 ) {
-    if resource_handles.is_all_done() {
-        next_screen.set(Screen::Gameplay);
-    } else {
-        next_screen.set(Screen::Loading);
-    }
+    let Some(replay) = load_latest_replay() else {
+        warn!("There is no replay to watch.");
+        return;
+    };
+
+    start_playback(
+        &mut commands,
+        replay,
+        RoundConfig::capture(
+            &seed,
+            &fixed_time,
+            &money,
+            &high_score,
+            &unlocked,
+            &level,
+            &separator_damage,
+        ),
+        &mut next_screen,
+    );
 }
 
 fn open_settings_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
@@ -1,8 +1,10 @@
 //! The game's menus and transitions between them.
 
 mod credits;
+mod level_select;
 mod main;
 mod pause;
+mod photo_mode;
 mod settings;
 mod shop;
 
@@ -13,10 +15,12 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_plugins((
         credits::plugin,
+        level_select::plugin,
         main::plugin,
         settings::plugin,
         shop::plugin,
         pause::plugin,
+        photo_mode::plugin,
     ));
 }
 
@@ -26,8 +30,10 @@ pub enum Menu {
     #[default]
     None,
     Main,
+    LevelSelect,
     Credits,
     Settings,
     Pause,
+    PhotoMode,
     Shop,
 }
@@ -25,6 +25,7 @@ fn spawn_pause_menu(mut commands: Commands, ui_assets: Res<UiAssets>) {
         children![
             widget::header("Game paused", &ui_assets),
             widget::button("Continue", close_menu, &ui_assets),
+            widget::button("Photo Mode", open_photo_mode, &ui_assets),
             widget::button("Settings", open_settings_menu, &ui_assets),
             widget::button("Quit to title", quit_to_title, &ui_assets),
             widget::button("Exit", exit_app, &ui_assets),
@@ -33,12 +34,17 @@ fn spawn_pause_menu(mut commands: Commands, ui_assets: Res<UiAssets>) {
         children![
             widget::header("Game paused", &ui_assets),
             widget::button("Continue", close_menu, &ui_assets),
+            widget::button("Photo Mode", open_photo_mode, &ui_assets),
             widget::button("Settings", open_settings_menu, &ui_assets),
             widget::button("Quit to title", quit_to_title, &ui_assets),
         ],
     ));
 }
 
+fn open_photo_mode(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::PhotoMode);
+}
+
 fn open_settings_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Settings);
 }
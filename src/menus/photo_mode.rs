@@ -0,0 +1,335 @@
+//! The photo mode, to take pictures of pileups.
+//!
+//! It is opened from the pause menu, so the round stays frozen. The HUD is hidden and a free
+//! perspective camera replaces the game camera: move it with WASD, Q and E (faster with Shift),
+//! look around by dragging with the mouse and change the field of view with the mouse wheel. Tab
+//! cycles through the looks, and Enter saves a screenshot into the pictures folder.
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_8};
+
+use bevy::{
+    core_pipeline::dof::{DepthOfField, DepthOfFieldMode},
+    input::{
+        common_conditions::input_just_pressed,
+        mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit},
+    },
+    prelude::*,
+    render::view::{ColorGrading, ColorGradingGlobal},
+    ui::Val::*,
+};
+
+use crate::{
+    camera::CameraRig,
+    game::ui::GameUi,
+    menus::Menu,
+    theme::widget::{self, UiAssets},
+};
+
+/// Flying speed in units per second.
+const FLY_SPEED: f32 = 30.;
+/// Factor of the flying speed while holding Shift.
+const FAST_FACTOR: f32 = 3.;
+/// Rotation in radians per pixel the mouse moves.
+const LOOK_SENSITIVITY: f32 = 0.003;
+const MIN_FOV: f32 = FRAC_PI_8;
+const MAX_FOV: f32 = FRAC_PI_2;
+/// Field of view change in radians per line scrolled.
+const FOV_STEP: f32 = 0.05;
+/// Focal distance when the camera looks above the horizon.
+const FAR_FOCUS: f32 = 200.;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PhotoCamera>();
+    app.register_type::<PhotoModeHint>();
+
+    app.add_systems(
+        OnEnter(Menu::PhotoMode),
+        (spawn_photo_camera, spawn_photo_mode_hint, hide_game_ui),
+    );
+    app.add_systems(OnExit(Menu::PhotoMode), (restore_game_camera, show_game_ui));
+    app.add_systems(
+        Update,
+        (
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+            cycle_photo_preset.run_if(input_just_pressed(KeyCode::Tab)),
+            fly_photo_camera,
+            #[cfg(not(target_family = "wasm"))]
+            save_screenshot.run_if(input_just_pressed(KeyCode::Enter)),
+        )
+            .chain()
+            .run_if(in_state(Menu::PhotoMode)),
+    );
+}
+
+/// Looks to choose from in the photo mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+enum PhotoPreset {
+    #[default]
+    Natural,
+    /// Blurs everything but the ground in the center of the view.
+    Miniature,
+    /// Warm evening light.
+    GoldenHour,
+    /// Cold blue light.
+    Night,
+    /// Black and white.
+    Noir,
+}
+
+impl PhotoPreset {
+    const ALL: [Self; 5] = [
+        Self::Natural,
+        Self::Miniature,
+        Self::GoldenHour,
+        Self::Night,
+        Self::Noir,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Natural => "Natural",
+            Self::Miniature => "Miniature",
+            Self::GoldenHour => "Golden Hour",
+            Self::Night => "Night",
+            Self::Noir => "Noir",
+        }
+    }
+
+    fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|preset| *preset == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn color_grading(self) -> ColorGrading {
+        let (exposure, temperature, post_saturation) = match self {
+            Self::Natural | Self::Miniature => (0., 0., 1.),
+            Self::GoldenHour => (0.3, 0.6, 1.2),
+            Self::Night => (-1.5, -0.5, 0.6),
+            Self::Noir => (0., 0., 0.),
+        };
+        ColorGrading {
+            global: ColorGradingGlobal {
+                exposure,
+                temperature,
+                post_saturation,
+                ..default()
+            },
+            ..default()
+        }
+    }
+
+    fn depth_of_field(self) -> Option<DepthOfField> {
+        (self == Self::Miniature).then(|| DepthOfField {
+            mode: DepthOfFieldMode::Gaussian,
+            aperture_f_stops: 1. / 32.,
+            ..default()
+        })
+    }
+}
+
+/// The free camera of the photo mode.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+struct PhotoCamera {
+    yaw: f32,
+    pitch: f32,
+    preset: PhotoPreset,
+}
+
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+struct PhotoModeHint;
+
+/// Replaces the game camera by a perspective camera at the same place.
+fn spawn_photo_camera(
+    mut commands: Commands,
+    game_camera: Single<(&mut Camera, &Transform), With<CameraRig>>,
+) {
+    let (mut camera, transform) = game_camera.into_inner();
+    camera.is_active = false;
+
+    let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+    let preset = PhotoPreset::default();
+    commands.spawn((
+        Name::new("Photo Camera"),
+        StateScoped(Menu::PhotoMode),
+        Camera3d::default(),
+        Projection::Perspective(PerspectiveProjection::default()),
+        Transform::from_translation(transform.translation).with_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            yaw,
+            pitch,
+            0.,
+        )),
+        PhotoCamera { yaw, pitch, preset },
+        preset.color_grading(),
+    ));
+}
+
+fn restore_game_camera(mut camera: Single<&mut Camera, With<CameraRig>>) {
+    camera.is_active = true;
+}
+
+fn hide_game_ui(mut game_ui: Query<&mut Visibility, With<GameUi>>) {
+    for mut visibility in &mut game_ui {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_game_ui(mut game_ui: Query<&mut Visibility, With<GameUi>>) {
+    for mut visibility in &mut game_ui {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+fn spawn_photo_mode_hint(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    commands.spawn((
+        Name::new("Photo Mode Hint"),
+        StateScoped(Menu::PhotoMode),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Px(10.),
+            left: Px(10.),
+            ..default()
+        },
+        children![(
+            PhotoModeHint,
+            widget::label(hint_text(PhotoPreset::default()), &ui_assets),
+        )],
+    ));
+}
+
+fn hint_text(preset: PhotoPreset) -> String {
+    #[cfg(not(target_family = "wasm"))]
+    let save = "  [Enter] save screenshot";
+    #[cfg(target_family = "wasm")]
+    let save = "";
+    format!(
+        "PHOTO MODE  Look: {}\n[WASD/QE] fly  [Drag] look  [Wheel] field of view  [Tab] look{save}  [Esc] back",
+        preset.name()
+    )
+}
+
+fn cycle_photo_preset(
+    mut commands: Commands,
+    camera: Single<(Entity, &mut PhotoCamera)>,
+    mut hint: Single<&mut Text, With<PhotoModeHint>>,
+) {
+    let (entity, mut photo_camera) = camera.into_inner();
+    photo_camera.preset = photo_camera.preset.next();
+    let preset = photo_camera.preset;
+
+    let mut entity = commands.entity(entity);
+    entity.insert(preset.color_grading());
+    match preset.depth_of_field() {
+        Some(depth_of_field) => entity.insert(depth_of_field),
+        None => entity.remove::<DepthOfField>(),
+    };
+    hint.0 = hint_text(preset);
+}
+
+/// Flies the photo camera in real time, as the game time stands still.
+fn fly_photo_camera(
+    camera: Single<(
+        &mut Transform,
+        &mut PhotoCamera,
+        &mut Projection,
+        Option<&mut DepthOfField>,
+    )>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    time: Res<Time<Real>>,
+) {
+    let (mut transform, mut photo_camera, mut projection, depth_of_field) = camera.into_inner();
+
+    if mouse.any_pressed([MouseButton::Left, MouseButton::Right]) {
+        photo_camera.yaw -= motion.delta.x * LOOK_SENSITIVITY;
+        photo_camera.pitch = (photo_camera.pitch - motion.delta.y * LOOK_SENSITIVITY)
+            .clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+    transform.rotation = Quat::from_euler(EulerRot::YXZ, photo_camera.yaw, photo_camera.pitch, 0.);
+
+    let axis = |positive: KeyCode, negative: KeyCode| {
+        keys.pressed(positive) as i32 as f32 - keys.pressed(negative) as i32 as f32
+    };
+    let direction = transform.forward() * axis(KeyCode::KeyW, KeyCode::KeyS)
+        + transform.right() * axis(KeyCode::KeyD, KeyCode::KeyA)
+        + Vec3::Y * axis(KeyCode::KeyE, KeyCode::KeyQ);
+    let speed = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        FLY_SPEED * FAST_FACTOR
+    } else {
+        FLY_SPEED
+    };
+    transform.translation += direction.normalize_or_zero() * speed * time.delta_secs();
+
+    let lines = match scroll.unit {
+        MouseScrollUnit::Pixel => scroll.delta.y / 100.0,
+        MouseScrollUnit::Line => scroll.delta.y,
+    };
+    if let Projection::Perspective(perspective) = projection.as_mut() {
+        perspective.fov = (perspective.fov - lines * FOV_STEP).clamp(MIN_FOV, MAX_FOV);
+    }
+
+    // Focus on the ground in the center of the view.
+    if let Some(mut depth_of_field) = depth_of_field {
+        let forward = transform.forward();
+        depth_of_field.focal_distance = if forward.y < 0. {
+            (transform.translation.y / -forward.y).min(FAR_FOCUS)
+        } else {
+            FAR_FOCUS
+        };
+    }
+}
+
+/// Saves a screenshot without the hint into the pictures folder.
+#[cfg(not(target_family = "wasm"))]
+fn save_screenshot(mut commands: Commands, mut hint: Single<&mut Visibility, With<PhotoModeHint>>) {
+    use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+
+    let directory = pictures_directory();
+    if let Err(err) = std::fs::create_dir_all(&directory) {
+        warn!("Could not create the screenshot folder {directory:?}: {err}");
+        return;
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = directory.join(format!("pileup-{timestamp}.png"));
+    info!("Saving screenshot to {path:?}");
+
+    // The hint is hidden in the frame the screenshot is taken of.
+    **hint = Visibility::Hidden;
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path))
+        .observe(
+            |_: Trigger<ScreenshotCaptured>,
+             mut hint: Query<&mut Visibility, With<PhotoModeHint>>| {
+                for mut visibility in &mut hint {
+                    *visibility = Visibility::Inherited;
+                }
+            },
+        );
+}
+
+/// The user's pictures folder, falling back to a folder next to the game.
+#[cfg(not(target_family = "wasm"))]
+fn pictures_directory() -> std::path::PathBuf {
+    use std::{env, path::PathBuf};
+
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    env::var_os("XDG_PICTURES_DIR")
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| PathBuf::from(home).join("Pictures")))
+        .unwrap_or_else(|| PathBuf::from("screenshots"))
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Pause);
+}
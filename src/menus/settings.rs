@@ -5,6 +5,9 @@
 use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
 
 use crate::{
+    audio::{AudioChannel, VolumeChannels},
+    camera::CameraSettings,
+    game::round::PhysicsRate,
     menus::Menu,
     screens::Screen,
     theme::{prelude::*, widget::UiAssets},
@@ -18,9 +21,18 @@ pub(super) fn plugin(app: &mut App) {
     );
 
     app.register_type::<GlobalVolumeLabel>();
+    app.register_type::<ChannelVolumeLabel>();
+    app.register_type::<PhysicsRateLabel>();
+    app.register_type::<CameraToggleLabel>();
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        (
+            update_global_volume_label,
+            update_channel_volume_labels,
+            update_physics_rate_label,
+            update_camera_toggle_labels,
+        )
+            .run_if(in_state(Menu::Settings)),
     );
 }
 
@@ -31,15 +43,16 @@ fn spawn_settings_menu(mut commands: Commands, ui_assets: Res<UiAssets>) {
         StateScoped(Menu::Settings),
         children![
             widget::header("Settings", &ui_assets),
+            volume_grid(&ui_assets),
             settings_grid(&ui_assets),
             widget::button("Back", go_back_on_click, &ui_assets),
         ],
     ));
 }
 
-fn settings_grid(ui_assets: &UiAssets) -> impl Bundle {
+fn volume_grid(ui_assets: &UiAssets) -> impl Bundle {
     (
-        Name::new("Settings Grid"),
+        Name::new("Volume Grid"),
         Node {
             display: Display::Grid,
             row_gap: Px(10.0),
@@ -56,6 +69,41 @@ fn settings_grid(ui_assets: &UiAssets) -> impl Bundle {
                 }
             ),
             global_volume_widget(ui_assets),
+            channel_volume_row(AudioChannel::Music, ui_assets),
+            channel_volume_widget(AudioChannel::Music, ui_assets),
+            channel_volume_row(AudioChannel::SoundEffects, ui_assets),
+            channel_volume_widget(AudioChannel::SoundEffects, ui_assets),
+            channel_volume_row(AudioChannel::Engines, ui_assets),
+            channel_volume_widget(AudioChannel::Engines, ui_assets),
+            channel_volume_row(AudioChannel::Ui, ui_assets),
+            channel_volume_widget(AudioChannel::Ui, ui_assets),
+        ],
+    )
+}
+
+fn settings_grid(ui_assets: &UiAssets) -> impl Bundle {
+    (
+        Name::new("Settings Grid"),
+        Node {
+            display: Display::Grid,
+            row_gap: Px(10.0),
+            column_gap: Px(30.0),
+            grid_template_columns: RepeatedGridTrack::px(2, 400.0),
+            ..default()
+        },
+        children![
+            (
+                widget::label("Physics Rate", ui_assets),
+                Node {
+                    justify_self: JustifySelf::End,
+                    ..default()
+                }
+            ),
+            physics_rate_widget(ui_assets),
+            camera_toggle_row(CameraToggle::EdgeScroll, ui_assets),
+            camera_toggle_widget(CameraToggle::EdgeScroll, ui_assets),
+            camera_toggle_row(CameraToggle::Shake, ui_assets),
+            camera_toggle_widget(CameraToggle::Shake, ui_assets),
         ],
     )
 }
@@ -108,6 +156,213 @@ fn update_global_volume_label(
     label.0 = format!("{percent:3.0}%");
 }
 
+fn channel_volume_row(channel: AudioChannel, ui_assets: &UiAssets) -> impl Bundle {
+    (
+        widget::label(format!("{} Volume", channel.name()), ui_assets),
+        Node {
+            justify_self: JustifySelf::End,
+            ..default()
+        },
+    )
+}
+
+fn channel_volume_widget(channel: AudioChannel, ui_assets: &UiAssets) -> impl Bundle {
+    (
+        Name::new(format!("{} Volume Widget", channel.name())),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small(
+                "-",
+                move |_: Trigger<Pointer<Click>>, mut channels: ResMut<VolumeChannels>| {
+                    step_channel_volume(&mut channels, channel, -0.1);
+                },
+                ui_assets
+            ),
+            (
+                Name::new("Current Volume"),
+                Node {
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label("", ui_assets), ChannelVolumeLabel(channel))],
+            ),
+            widget::button_small(
+                "+",
+                move |_: Trigger<Pointer<Click>>, mut channels: ResMut<VolumeChannels>| {
+                    step_channel_volume(&mut channels, channel, 0.1);
+                },
+                ui_assets
+            ),
+        ],
+    )
+}
+
+fn step_channel_volume(channels: &mut VolumeChannels, channel: AudioChannel, step: f32) {
+    let volume = channels.get_mut(channel);
+    *volume = Volume::Linear((volume.to_linear() + step).clamp(0.0, 1.0));
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ChannelVolumeLabel(AudioChannel);
+
+fn update_channel_volume_labels(
+    channels: Res<VolumeChannels>,
+    mut labels: Query<(&mut Text, &ChannelVolumeLabel)>,
+) {
+    for (mut text, label) in &mut labels {
+        let percent = 100.0 * channels.get(label.0).to_linear();
+        text.0 = format!("{percent:3.0}%");
+    }
+}
+
+fn physics_rate_widget(ui_assets: &UiAssets) -> impl Bundle {
+    (
+        Name::new("Physics Rate Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("-", lower_physics_rate, ui_assets),
+            (
+                Name::new("Current Physics Rate"),
+                Node {
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label("", ui_assets), PhysicsRateLabel)],
+            ),
+            widget::button_small("+", raise_physics_rate, ui_assets),
+        ],
+    )
+}
+
+fn lower_physics_rate(_: Trigger<Pointer<Click>>, mut physics_rate: ResMut<PhysicsRate>) {
+    step_physics_rate(&mut physics_rate, -1);
+}
+
+fn raise_physics_rate(_: Trigger<Pointer<Click>>, mut physics_rate: ResMut<PhysicsRate>) {
+    step_physics_rate(&mut physics_rate, 1);
+}
+
+/// Selects the neighbouring option of the current rate.
+fn step_physics_rate(physics_rate: &mut PhysicsRate, step: isize) {
+    let options = PhysicsRate::OPTIONS;
+    let current = options
+        .iter()
+        .position(|rate| *rate == physics_rate.0)
+        .unwrap_or_default();
+    let next = current.saturating_add_signed(step).min(options.len() - 1);
+    physics_rate.0 = options[next];
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PhysicsRateLabel;
+
+fn update_physics_rate_label(
+    physics_rate: Res<PhysicsRate>,
+    screen: Res<State<Screen>>,
+    mut label: Single<&mut Text, With<PhysicsRateLabel>>,
+) {
+    let rate = physics_rate.0;
+    label.0 = if screen.get() == &Screen::Gameplay {
+        format!("{rate:.0} Hz (next round)")
+    } else {
+        format!("{rate:.0} Hz")
+    };
+}
+
+/// The camera settings which are switched on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+enum CameraToggle {
+    EdgeScroll,
+    Shake,
+}
+
+impl CameraToggle {
+    fn name(self) -> &'static str {
+        match self {
+            Self::EdgeScroll => "Edge Scrolling",
+            Self::Shake => "Screen Shake",
+        }
+    }
+
+    fn value(self, camera_settings: &CameraSettings) -> bool {
+        match self {
+            Self::EdgeScroll => camera_settings.edge_scroll,
+            Self::Shake => camera_settings.shake,
+        }
+    }
+
+    fn value_mut(self, camera_settings: &mut CameraSettings) -> &mut bool {
+        match self {
+            Self::EdgeScroll => &mut camera_settings.edge_scroll,
+            Self::Shake => &mut camera_settings.shake,
+        }
+    }
+}
+
+fn camera_toggle_row(toggle: CameraToggle, ui_assets: &UiAssets) -> impl Bundle {
+    (
+        widget::label(toggle.name(), ui_assets),
+        Node {
+            justify_self: JustifySelf::End,
+            ..default()
+        },
+    )
+}
+
+fn camera_toggle_widget(toggle: CameraToggle, ui_assets: &UiAssets) -> impl Bundle {
+    let switch = move |_: Trigger<Pointer<Click>>, mut camera_settings: ResMut<CameraSettings>| {
+        let value = toggle.value_mut(&mut camera_settings);
+        *value = !*value;
+    };
+    (
+        Name::new(format!("{} Widget", toggle.name())),
+        Node {
+            justify_self: JustifySelf::Start,
+            ..default()
+        },
+        children![
+            widget::button_small("<", switch, ui_assets),
+            (
+                Name::new(format!("Current {}", toggle.name())),
+                Node {
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label("", ui_assets), CameraToggleLabel(toggle))],
+            ),
+            widget::button_small(">", switch, ui_assets),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct CameraToggleLabel(CameraToggle);
+
+fn update_camera_toggle_labels(
+    camera_settings: Res<CameraSettings>,
+    mut labels: Query<(&mut Text, &CameraToggleLabel)>,
+) {
+    for (mut text, label) in &mut labels {
+        text.0 = if label.0.value(&camera_settings) {
+            "On".into()
+        } else {
+            "Off".into()
+        };
+    }
+}
+
 fn go_back_on_click(
     _: Trigger<Pointer<Click>>,
     screen: Res<State<Screen>>,
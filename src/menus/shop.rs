@@ -3,13 +3,18 @@
 use bevy::{prelude::*, ui::Val::*};
 
 use crate::{
-    audio::music,
     game::{
         car::CarAssets,
+        level::SelectedLevel,
         pertubator::{Money, Pertubator, PertubatorAssets, UnlockedPertubators},
+        points_money::HighScore,
+        replay::{LastReplay, start_playback},
+        road::SeparatorDamage,
+        round::{RoundConfig, RoundSeed},
+        tuning::GameTuning,
         ui::MoneyUi,
     },
-    menus::{Menu, credits::CreditsAssets},
+    menus::Menu,
     screens::*,
     theme::widget::{self, UiAssets, button_base, label},
 };
@@ -20,12 +25,17 @@ use rand::Rng;
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         OnEnter(Menu::Shop),
-        (spawn_shop_menu, update_unlock_displays).chain(),
+        (
+            spawn_shop_menu,
+            update_unlock_displays,
+            update_repair_separators_display,
+        )
+            .chain(),
     );
 
     app.add_systems(
         OnEnter(Screen::Shop),
-        (update_clear_color, spawn_rotating_cars, start_shop_music),
+        (update_clear_color, spawn_rotating_cars),
     );
     app.add_systems(Update, rotate.run_if(in_state(Screen::Shop)));
 
@@ -34,9 +44,16 @@ pub(super) fn plugin(app: &mut App) {
         update_unlock_displays
             .run_if(in_state(Menu::Shop).and(resource_changed::<UnlockedPertubators>)),
     );
+    app.add_systems(
+        Update,
+        update_repair_separators_display
+            .run_if(in_state(Menu::Shop).and(resource_changed::<SeparatorDamage>)),
+    );
 
     app.register_type::<UnlockPertubatorDisplay>();
     app.register_type::<UnlockPertubatorDisplayLabel>();
+    app.register_type::<RepairSeparatorsLabel>();
+    app.register_type::<RepairSeparatorsButton>();
 }
 
 fn spawn_shop_menu(
@@ -75,6 +92,7 @@ fn spawn_shop_menu(
                 unlock_pertubator_widget(&ui_assets, Pertubator::Nails, &pertubator_assets),
                 unlock_pertubator_widget(&ui_assets, Pertubator::Spring, &pertubator_assets),
                 unlock_pertubator_widget(&ui_assets, Pertubator::Barrel, &pertubator_assets),
+                repair_separators_widget(&ui_assets),
                 (
                     Node {
                         flex_direction: FlexDirection::Row,
@@ -87,6 +105,21 @@ fn spawn_shop_menu(
                         next_round_button(&ui_assets),
                     ],
                 ),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        column_gap: Px(60.),
+                        ..default()
+                    },
+                    #[cfg(not(target_family = "wasm"))]
+                    children![
+                        watch_replay_button(&ui_assets),
+                        save_replay_button(&ui_assets)
+                    ],
+                    #[cfg(target_family = "wasm")]
+                    children![watch_replay_button(&ui_assets)],
+                ),
             ],
         )],
     ));
@@ -140,6 +173,84 @@ fn back_to_title_button(ui_assets: &UiAssets) -> impl Bundle {
     )
 }
 
+fn watch_replay(
+    _: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    last_replay: Res<LastReplay>,
+    mut next_menu: ResMut<NextState<Menu>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    seed: Res<RoundSeed>,
+    fixed_time: Res<Time<Fixed>>,
+    money: Res<Money>,
+    high_score: Res<HighScore>,
+    unlocked: Res<UnlockedPertubators>,
+    level: Res<SelectedLevel>,
+    separator_damage: Res<SeparatorDamage>,
+) {
+    let Some(replay) = last_replay.0.clone() else {
+        return;
+    };
+
+    next_menu.set(Menu::None);
+    start_playback(
+        &mut commands,
+        replay,
+        RoundConfig::capture(
+            &seed,
+            &fixed_time,
+            &money,
+            &high_score,
+            &unlocked,
+            &level,
+            &separator_damage,
+        ),
+        &mut next_screen,
+    );
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_replay(_: Trigger<Pointer<Click>>, last_replay: Res<LastReplay>) {
+    let Some(replay) = &last_replay.0 else {
+        return;
+    };
+
+    match crate::game::replay::save_replay(replay) {
+        Ok(path) => info!("Saved replay to {}", path.display()),
+        Err(err) => error!("Could not save replay: {err}"),
+    }
+}
+
+fn watch_replay_button(ui_assets: &UiAssets) -> impl Bundle {
+    button_base(
+        "Watch replay",
+        watch_replay,
+        Node {
+            width: Px(310.),
+            height: Px(70.),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ui_assets,
+    )
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_replay_button(ui_assets: &UiAssets) -> impl Bundle {
+    button_base(
+        "Save replay",
+        save_replay,
+        Node {
+            width: Px(310.),
+            height: Px(70.),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ui_assets,
+    )
+}
+
 fn unlock_pertubator_widget(
     ui_assets: &UiAssets,
     pertubator: Pertubator,
@@ -246,6 +357,93 @@ fn update_unlock_displays(
     }
 }
 
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RepairSeparatorsLabel;
+
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RepairSeparatorsButton;
+
+fn repair_separators_widget(ui_assets: &UiAssets) -> impl Bundle {
+    (
+        Name::new("Repair Widget"),
+        Node {
+            justify_content: JustifyContent::SpaceBetween,
+            height: Px(80.),
+            ..default()
+        },
+        children![
+            (
+                label("Separators", ui_assets),
+                Node {
+                    align_self: AlignSelf::Center,
+                    ..default()
+                },
+            ),
+            (
+                // Filled in by `update_repair_separators_display`.
+                label("", ui_assets),
+                Node {
+                    align_self: AlignSelf::Center,
+                    ..default()
+                },
+                RepairSeparatorsLabel,
+            ),
+            (
+                button_base(
+                    "REPAIR",
+                    repair_clicked,
+                    Node {
+                        width: Px(220.0),
+                        height: Px(70.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    ui_assets,
+                ),
+                RepairSeparatorsButton,
+            ),
+        ],
+    )
+}
+
+fn repair_clicked(
+    _: Trigger<Pointer<Click>>,
+    mut separator_damage: ResMut<SeparatorDamage>,
+    mut money: ResMut<Money>,
+    tuning: Res<GameTuning>,
+) {
+    let cost = separator_damage.repair_cost(&tuning);
+    if money.0 >= cost {
+        money.0 -= cost;
+        separator_damage.damage.clear();
+    }
+}
+
+fn update_repair_separators_display(
+    mut commands: Commands,
+    mut labels: Query<&mut Text, With<RepairSeparatorsLabel>>,
+    buttons: Query<Entity, With<RepairSeparatorsButton>>,
+    separator_damage: Res<SeparatorDamage>,
+    tuning: Res<GameTuning>,
+) {
+    let cost = separator_damage.repair_cost(&tuning);
+    for mut text in &mut labels {
+        text.0 = if cost > 0 {
+            format!("Repair for {cost}")
+        } else {
+            "Intact!".into()
+        };
+    }
+    if cost == 0 {
+        for entity in &buttons {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
 #[derive(Debug, Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct Rotating;
@@ -282,11 +480,3 @@ fn rotate(mut transforms: Query<&mut Transform, With<Rotating>>, time: Res<Time>
 fn update_clear_color(mut clear_color: ResMut<ClearColor>) {
     clear_color.0 = DARK_KHAKI.into();
 }
-
-fn start_shop_music(mut commands: Commands, credits_music: Res<CreditsAssets>) {
-    commands.spawn((
-        Name::new("Shop Music"),
-        StateScoped(Screen::Shop),
-        music(credits_music.music.clone()),
-    ));
-}
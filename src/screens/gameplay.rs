@@ -3,11 +3,22 @@
 use avian3d::prelude::{Physics, PhysicsTime};
 use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
 
-use crate::{Pause, game::ui::spawn_game_ui, menus::Menu, screens::Screen};
+use crate::{
+    Pause,
+    game::{level::generate_endless_highway, ui::spawn_game_ui},
+    menus::Menu,
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     /* TODO: Spawn level etc. */
-    app.add_systems(OnEnter(Screen::Gameplay), (spawn_game_ui, spawn_tutorial));
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (
+            spawn_game_ui.after(generate_endless_highway),
+            spawn_tutorial,
+        ),
+    );
 
     // Toggle pause on key press.
     app.add_systems(
@@ -30,6 +41,9 @@ pub(super) fn plugin(app: &mut App) {
         OnEnter(Menu::None),
         unpause.run_if(in_state(Screen::Gameplay)),
     );
+    // Photos are taken of the paused round without the overlay.
+    app.add_systems(OnEnter(Menu::PhotoMode), hide_pause_overlay);
+    app.add_systems(OnExit(Menu::PhotoMode), show_pause_overlay);
 
     app.register_type::<TutorialTimer>();
     app.init_resource::<TutorialTimer>();
@@ -74,9 +88,25 @@ fn spawn_pause_overlay(mut commands: Commands) {
         GlobalZIndex(1),
         BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
         StateScoped(Pause(true)),
+        PauseOverlay,
     ));
 }
 
+#[derive(Component)]
+struct PauseOverlay;
+
+fn hide_pause_overlay(mut overlay: Query<&mut Visibility, With<PauseOverlay>>) {
+    for mut visibility in &mut overlay {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_pause_overlay(mut overlay: Query<&mut Visibility, With<PauseOverlay>>) {
+    for mut visibility in &mut overlay {
+        *visibility = Visibility::Inherited;
+    }
+}
+
 fn open_pause_menu(mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Pause);
 }
@@ -1,16 +1,22 @@
-//! A loading screen during which game assets are loaded if necessary.
-//! This reduces stuttering, especially for audio on Wasm.
+//! A loading screen during which game assets and the selected level's music are loaded if
+//! necessary. This reduces stuttering, especially for audio on Wasm.
 
 use bevy::prelude::*;
 
-use crate::{asset_tracking::ResourceHandles, screens::Screen, theme::prelude::*};
+use crate::{
+    asset_tracking::ResourceHandles, game::level::LevelMusic, screens::Screen, theme::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Loading), spawn_loading_screen);
 
     app.add_systems(
         Update,
-        enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
+        enter_gameplay_screen.run_if(
+            in_state(Screen::Loading)
+                .and(all_assets_loaded)
+                .and(level_music_loaded),
+        ),
     );
 }
 
@@ -29,3 +35,10 @@ fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
 fn all_assets_loaded(resource_handles: Res<ResourceHandles>) -> bool {
     resource_handles.is_all_done()
 }
+
+fn level_music_loaded(
+    level_music: Option<Res<LevelMusic>>,
+    asset_server: Res<AssetServer>,
+) -> bool {
+    level_music.is_some_and(|music| music.is_loaded(&asset_server))
+}
@@ -0,0 +1,205 @@
+//! The music of the screens and menus.
+//!
+//! [`SCREEN_TRACKS`] and [`MENU_TRACKS`] tell which track plays where. On every change of the
+//! screen or menu, the [`Soundtrack`] crossfades to the track of the new one. Tracks fading out are
+//! paused instead of stopped, so they resume where they left off when they come back.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    asset_tracking::LoadResource,
+    audio::{VolumeFade, music},
+    game::{level::LevelMusic, music::level_music},
+    menus::Menu,
+    screens::Screen,
+};
+
+/// Real seconds a crossfade takes.
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+/// The tracks of the screens. Screens without an entry keep the music playing.
+const SCREEN_TRACKS: &[(Screen, Option<Track>)] = &[
+    (Screen::Title, None),
+    (Screen::Gameplay, Some(Track::Level)),
+];
+
+/// The tracks of the menus, taking precedence over the screen they are opened on. Menus without
+/// an entry play the track of the screen.
+const MENU_TRACKS: &[(Menu, Option<Track>)] = &[(Menu::Credits, Some(Track::Credits))];
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<SoundtrackAssets>();
+    app.load_resource::<SoundtrackAssets>();
+    app.register_type::<Crossfade>();
+    app.init_resource::<Soundtrack>();
+
+    app.add_systems(
+        Update,
+        (
+            choose_track.run_if(
+                state_changed::<Screen>
+                    .or(state_changed::<Menu>)
+                    .or(resource_exists_and_changed::<LevelMusic>),
+            ),
+            crossfade,
+        )
+            .chain(),
+    );
+}
+
+/// A piece of music of the soundtrack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Track {
+    /// The music of the current level, see [`LevelMusic`].
+    Level,
+    Credits,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+struct SoundtrackAssets {
+    #[dependency]
+    credits: Handle<AudioSource>,
+}
+
+impl FromWorld for SoundtrackAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            credits: assets.load("audio/music/FreakyWaves - CrashThemAll_credits.ogg"),
+        }
+    }
+}
+
+/// Owns the players of the tracks played so far.
+#[derive(Debug, Default, Resource)]
+struct Soundtrack {
+    players: HashMap<AssetId<AudioSource>, Entity>,
+    /// The player of the level music with its layers, replaced when a level has other music.
+    level: Option<(LevelMusic, Entity)>,
+}
+
+/// Fades a track in or out, with its child layers.
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component)]
+struct Crossfade {
+    /// 1 to fade in, 0 to fade out.
+    target: f32,
+}
+
+/// Fades in the track of the current screen or menu, and fades out the others.
+fn choose_track(
+    mut commands: Commands,
+    mut soundtrack: ResMut<Soundtrack>,
+    mut players: Query<&mut Crossfade>,
+    screen: Res<State<Screen>>,
+    menu: Res<State<Menu>>,
+    assets: Option<Res<SoundtrackAssets>>,
+    level: Option<Res<LevelMusic>>,
+) {
+    let menu_track = MENU_TRACKS
+        .iter()
+        .find(|(entry, _)| entry == menu.get())
+        .map(|(_, track)| *track);
+    let screen_track = SCREEN_TRACKS
+        .iter()
+        .find(|(entry, _)| entry == screen.get())
+        .map(|(_, track)| *track);
+    let Some(track) = menu_track.or(screen_track) else {
+        return;
+    };
+
+    let player = match track {
+        Some(Track::Level) => {
+            level.map(|level| level_player(&mut commands, &mut soundtrack, &level))
+        }
+        Some(Track::Credits) => assets.map(|assets| {
+            let handle = assets.credits.clone();
+            *soundtrack.players.entry(handle.id()).or_insert_with(|| {
+                commands
+                    .spawn((
+                        Name::new("Soundtrack Credits"),
+                        music(handle),
+                        Crossfade { target: 1. },
+                        VolumeFade(0.),
+                    ))
+                    .id()
+            })
+        }),
+        None => None,
+    };
+
+    let level_player = soundtrack.level.as_ref().map(|(_, player)| *player);
+    for other in soundtrack.players.values().copied().chain(level_player) {
+        if let Ok(mut crossfade) = players.get_mut(other) {
+            crossfade.target = if Some(other) == player { 1. } else { 0. };
+        }
+    }
+}
+
+/// The player of the level music, spawning a new one if the music differs from the last level.
+fn level_player(
+    commands: &mut Commands,
+    soundtrack: &mut Soundtrack,
+    music: &LevelMusic,
+) -> Entity {
+    if let Some((playing, player)) = &soundtrack.level {
+        if playing == music {
+            return *player;
+        }
+        commands.entity(*player).despawn();
+    }
+    let player = commands
+        .spawn((
+            Name::new("Soundtrack Level"),
+            level_music(music),
+            Crossfade { target: 1. },
+            VolumeFade(0.),
+        ))
+        .id();
+    soundtrack.level = Some((music.clone(), player));
+    player
+}
+
+/// Moves the fades toward their targets in real time, pausing the tracks which are faded out.
+fn crossfade(
+    mut players: Query<(
+        &Crossfade,
+        &mut VolumeFade,
+        Option<&AudioSink>,
+        Option<&Children>,
+    )>,
+    mut layers: Query<(&mut VolumeFade, Option<&AudioSink>), Without<Crossfade>>,
+    time: Res<Time<Real>>,
+) {
+    let step = time.delta_secs() / CROSSFADE_SECONDS;
+    for (crossfade, mut fade, sink, children) in &mut players {
+        if fade.0 == crossfade.target && sink.is_none_or(|sink| sink.is_paused() == (fade.0 == 0.))
+        {
+            continue;
+        }
+        fade.0 += (crossfade.target - fade.0).clamp(-step, step);
+        let paused = fade.0 == 0.;
+
+        for child in children.into_iter().flatten() {
+            let Ok((mut layer_fade, layer_sink)) = layers.get_mut(*child) else {
+                continue;
+            };
+            layer_fade.0 = fade.0;
+            if let Some(layer_sink) = layer_sink {
+                set_paused(layer_sink, paused);
+            }
+        }
+        if let Some(sink) = sink {
+            set_paused(sink, paused);
+        }
+    }
+}
+
+fn set_paused(sink: &AudioSink, paused: bool) {
+    if paused {
+        sink.pause();
+    } else {
+        sink.play();
+    }
+}
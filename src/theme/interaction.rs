@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{asset_tracking::LoadResource, audio::sound_effect};
+use crate::{asset_tracking::LoadResource, audio::ui_sound};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<InteractionPalette>();
@@ -69,7 +69,7 @@ fn play_on_hover_sound_effect(
     };
 
     if interaction_query.contains(trigger.target()) {
-        commands.spawn(sound_effect(interaction_assets.hover.clone()));
+        commands.spawn(ui_sound(interaction_assets.hover.clone()));
     }
 }
 
@@ -84,6 +84,6 @@ fn play_on_click_sound_effect(
     };
 
     if interaction_query.contains(trigger.target()) {
-        commands.spawn(sound_effect(interaction_assets.click.clone()));
+        commands.spawn(ui_sound(interaction_assets.click.clone()));
     }
 }